@@ -0,0 +1,257 @@
+//! Command line control of the shell over its USB serial port
+//!
+//! Every request is a `juicy_core::console::Command`, formatted exactly as
+//! the firmware parses it, and streamed frames use the same Adalight
+//! encoding the firmware decodes.
+
+use std::env;
+use std::io::{ErrorKind, Read, Write};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serialport::{SerialPort, SerialPortType};
+use smart_leds::RGB8;
+
+use juicy_core::console::{parse, Command, USB_PID, USB_VID};
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::stream::adalight_header;
+
+/// A reply is complete once the shell has been quiet this long
+const REPLY_IDLE: Duration = Duration::from_millis(100);
+/// Longest wait for the first byte of a reply
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+const STREAM_FPS: u64 = 50;
+
+const USAGE: &str = "\
+usage: juicy-cli [--port <path>] <command>
+
+commands:
+  list                                   registered effects
+  status                                 current effect and settings
+  select <name>                          switch to an effect
+  brightness <0-255>                     master brightness
+  duration <seconds>                     how long the current effect plays
+  params <delay_ms> [<param>]            restart the current effect with new timing
+  playlist                               show the playlist
+  playlist [--order <order>] <name>:<seconds>...
+                                         replace the playlist and play it,
+                                         order is sequential, shuffle or random
+  stream [<seconds>]                     stream a rainbow test pattern, default 10s
+  send <line>                            any console line, see `send help`
+
+The port defaults to the first one with the shell's USB ids.";
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(mut args: Vec<String>) -> Result<(), String> {
+    let port_path = match args.iter().position(|arg| arg == "--port") {
+        Some(index) if index + 1 < args.len() => {
+            let path = args.remove(index + 1);
+            args.remove(index);
+            Some(path)
+        }
+        Some(_) => return Err("--port needs a path".into()),
+        None => None,
+    };
+
+    let Some((command, args)) = args.split_first() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    if matches!(command.as_str(), "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let mut shell = Shell::open(port_path.as_deref())?;
+
+    match (command.as_str(), args.as_slice()) {
+        ("list", []) => shell.print(Command::List),
+        ("status", []) => shell.print(Command::Status),
+        ("select", [name]) => shell.print(Command::Effect(name)),
+        ("brightness", [brightness]) => shell.print(Command::Brightness(number(brightness)?)),
+        ("duration", [duration]) => shell.print(Command::Duration(number(duration)?)),
+        ("params", [delay]) => shell.print(Command::Params { delay_ms: number(delay)?, param: None }),
+        ("params", [delay, param]) => shell.print(Command::Params {
+            delay_ms: number(delay)?,
+            param: Some(number(param)?),
+        }),
+        ("playlist", []) => shell.print(Command::Playlist),
+        ("playlist", entries) => push_playlist(&mut shell, entries),
+        ("stream", []) => stream_test_pattern(&mut shell, 10),
+        ("stream", [seconds]) => stream_test_pattern(&mut shell, number(seconds)?),
+        ("send", words) if !words.is_empty() => {
+            let line = words.join(" ");
+            shell.print(parse(&line)?)
+        }
+        _ => Err(format!("bad arguments for {}, see juicy-cli help", command)),
+    }
+}
+
+fn number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("{} is not a valid number", word))
+}
+
+/// Clears the playlist, adds each `name:seconds` entry and starts it
+fn push_playlist(shell: &mut Shell, mut entries: &[&str]) -> Result<(), String> {
+    let mut order = None;
+    if let ["--order", name, rest @ ..] = entries {
+        order = match parse(&format!("playlist order {}", name))? {
+            Command::PlaylistOrder(order) => Some(order),
+            _ => None,
+        };
+        entries = rest;
+    }
+
+    // Check every entry before touching the shell's playlist
+    let mut parsed = Vec::new();
+    for entry in entries {
+        let (name, seconds) = entry
+            .split_once(':')
+            .ok_or_else(|| format!("{} should be <name>:<seconds>", entry))?;
+        parsed.push(Command::PlaylistAdd { name, duration_sec: number(seconds)? });
+    }
+    if parsed.is_empty() {
+        return Err("the playlist needs at least one entry".into());
+    }
+
+    shell.request(Command::PlaylistClear)?;
+    for command in parsed {
+        shell.request(command)?;
+    }
+    if let Some(order) = order {
+        shell.request(Command::PlaylistOrder(order))?;
+    }
+    shell.request(Command::PlaylistPlay)?;
+
+    shell.print(Command::Playlist)
+}
+
+/// A rainbow turning along the spiral, one Adalight frame per tick
+fn stream_test_pattern(shell: &mut Shell, seconds: u64) -> Result<(), String> {
+    let start = Instant::now();
+    let frame_time = Duration::from_millis(1000 / STREAM_FPS);
+    let mut frame = Vec::with_capacity(6 + NUM_BLADES * 3);
+    let mut offset: u8 = 0;
+
+    println!("streaming for {}s", seconds);
+    while start.elapsed() < Duration::from_secs(seconds) {
+        frame.clear();
+        frame.extend_from_slice(&adalight_header(NUM_BLADES as u16));
+        for blade in 0..NUM_BLADES {
+            let color = wheel(offset.wrapping_add((blade * 256 / NUM_BLADES) as u8));
+            frame.extend_from_slice(&[color.r, color.g, color.b]);
+        }
+
+        shell.write(&frame)?;
+        offset = offset.wrapping_add(2);
+        thread::sleep(frame_time);
+    }
+
+    println!("done, the shell goes back to its playlist shortly");
+    Ok(())
+}
+
+/// Fully saturated hue, 0 red, 85 green, 170 blue
+fn wheel(hue: u8) -> RGB8 {
+    let rising = (hue % 85) * 3;
+    let falling = 255 - rising;
+    match hue / 85 {
+        0 => RGB8::new(falling, rising, 0),
+        1 => RGB8::new(0, falling, rising),
+        _ => RGB8::new(rising, 0, falling),
+    }
+}
+
+/// The shell's serial port
+struct Shell {
+    port: Box<dyn SerialPort>,
+}
+
+impl Shell {
+    fn open(path: Option<&str>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path.to_string(),
+            None => find_port()?,
+        };
+
+        // The baud rate means nothing to a USB CDC port
+        let port = serialport::new(&path, 115_200)
+            .timeout(REPLY_IDLE)
+            .open()
+            .map_err(|error| format!("can't open {}: {}", path, error))?;
+
+        Ok(Self { port })
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.port
+            .write_all(bytes)
+            .map_err(|error| format!("write failed: {}", error))
+    }
+
+    /// Sends a command, returning the reply or its error line
+    fn request(&mut self, command: Command) -> Result<String, String> {
+        self.write(format!("{}\n", command).as_bytes())?;
+
+        let reply = self.read_reply()?;
+        match reply.lines().find_map(|line| line.strip_prefix("error: ")) {
+            Some(error) => Err(error.to_string()),
+            None => Ok(reply),
+        }
+    }
+
+    fn print(&mut self, command: Command) -> Result<(), String> {
+        print!("{}", self.request(command)?);
+        Ok(())
+    }
+
+    /// Reads until the shell goes quiet
+    fn read_reply(&mut self) -> Result<String, String> {
+        let start = Instant::now();
+        let mut reply = Vec::new();
+        let mut buffer = [0u8; 256];
+
+        loop {
+            match self.port.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => reply.extend_from_slice(&buffer[..count]),
+                Err(error) if error.kind() == ErrorKind::TimedOut => {
+                    if !reply.is_empty() {
+                        break;
+                    }
+                    if start.elapsed() > REPLY_TIMEOUT {
+                        return Err("no reply from the shell".into());
+                    }
+                }
+                Err(error) => return Err(format!("read failed: {}", error)),
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&reply).replace("\r\n", "\n"))
+    }
+}
+
+fn find_port() -> Result<String, String> {
+    let ports = serialport::available_ports().map_err(|error| format!("can't list serial ports: {}", error))?;
+
+    ports
+        .into_iter()
+        .find(|port| match &port.port_type {
+            SerialPortType::UsbPort(usb) => usb.vid == USB_VID && usb.pid == USB_PID,
+            _ => false,
+        })
+        .map(|port| port.port_name)
+        .ok_or_else(|| "no shell found, is it plugged in? Try --port".into())
+}
@@ -0,0 +1,116 @@
+//! APA102 / SK9822 bitstream encoding
+//!
+//! These strips have a separate clock line, so timing doesn't matter and
+//! every byte goes out as it is: a zero start frame, four bytes per LED
+//! and an end frame to clock the data through the whole strip. Each LED
+//! also has a 5-bit global brightness, which is used to keep detail in
+//! dim colors that 8 bits after gamma would flatten.
+
+use smart_leds::RGB8;
+
+use crate::bitstream::Bitstream;
+use crate::math8::{nscale8, qadd8};
+use crate::output::{ColorOrder, OutputStage, Pixel};
+
+/// Zero start frame
+pub const START_BYTES: usize = 4;
+
+/// Brightness byte then blue, green, red
+pub const BYTES_PER_LED: usize = 4;
+
+/// Order the channels go out in
+pub const WIRE_ORDER: ColorOrder = ColorOrder::BGR;
+
+/// Highest global brightness
+pub const MAX_BRIGHTNESS: u8 = 31;
+
+/// Zero bytes after the LEDs, a reset frame for SK9822 then half a clock
+/// per LED to push the data to the end of the strip
+pub const fn end_bytes(leds: usize) -> usize {
+    4 + leds.div_ceil(16)
+}
+
+/// Size of the bitstream for `leds` LEDs
+pub const fn buffer_len(leds: usize) -> usize {
+    START_BYTES + (leds * BYTES_PER_LED) + end_bytes(leds)
+}
+
+/// An LED's color and its 5-bit global brightness
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Apa102Color {
+    pub color: RGB8,
+    pub brightness: u8,
+}
+
+/// Picks the lowest global brightness that still fits the brightest
+/// channel, then scales the 16-bit gamma corrected channels up to match.
+/// That already keeps dim levels apart, so there is no dithering.
+impl Pixel for Apa102Color {
+    fn render(color: RGB8, white: u8, output: &OutputStage) -> Self {
+        let color = RGB8::new(qadd8(color.r, white), qadd8(color.g, white), qadd8(color.b, white));
+        let channels = output.correct16(color);
+
+        let peak = channels.iter().copied().max().unwrap_or(0) as u32;
+        let brightness = ((peak * MAX_BRIGHTNESS as u32).div_ceil(65535)).max(1);
+
+        // 65535 at full brightness is 255
+        let divisor = brightness * 257;
+        let [r, g, b] = channels.map(|channel| {
+            let scaled = (channel as u32 * MAX_BRIGHTNESS as u32 + divisor / 2) / divisor;
+            scaled.min(255) as u8
+        });
+
+        Self {
+            color: output.reorder(RGB8::new(r, g, b)),
+            brightness: brightness as u8,
+        }
+    }
+
+    fn level(&self) -> u32 {
+        let color = self.color;
+        let sum = color.r as u32 + color.g as u32 + color.b as u32;
+        (sum * self.brightness.min(MAX_BRIGHTNESS) as u32) / MAX_BRIGHTNESS as u32
+    }
+
+    fn scale(self, scale: u8) -> Self {
+        Self {
+            color: nscale8(self.color, scale),
+            brightness: self.brightness,
+        }
+    }
+}
+
+impl Bitstream for Apa102Color {
+    fn encode<I>(leds: I, buffer: &mut [u8]) -> Result<usize, &'static str>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        if buffer.len() < buffer_len(0) {
+            return Err("buffer too small for an empty frame")
+        }
+
+        buffer[..START_BYTES].fill(0);
+        let mut count = 0;
+
+        for led in leds {
+            let start = START_BYTES + count * BYTES_PER_LED;
+            if buffer_len(count + 1) > buffer.len() {
+                return Err("too many LEDs for the buffer")
+            }
+
+            let [first, second, third] = WIRE_ORDER.arrange(led.color);
+            buffer[start..start + BYTES_PER_LED].copy_from_slice(&[
+                0b1110_0000 | led.brightness.min(MAX_BRIGHTNESS),
+                first,
+                second,
+                third,
+            ]);
+
+            count += 1;
+        }
+
+        buffer[START_BYTES + count * BYTES_PER_LED..].fill(0);
+
+        Ok(count)
+    }
+}
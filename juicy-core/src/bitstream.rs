@@ -0,0 +1,13 @@
+//! Colors that can be prerendered into an SPI bitstream
+//!
+//! Lets the firmware's DMA driver clock out any supported LED type, the
+//! strip chip only changes how a frame is encoded.
+
+/// A color type with a matching SPI encoding
+pub trait Bitstream: Copy {
+    /// Renders `leds` into `buffer`, clearing whatever is left over so the
+    /// strip latches. Returns the number of LEDs written.
+    fn encode<I>(leds: I, buffer: &mut [u8]) -> Result<usize, &'static str>
+    where
+        I: IntoIterator<Item = Self>;
+}
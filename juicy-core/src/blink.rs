@@ -0,0 +1,85 @@
+//! Blink patterns for single blades
+//!
+//! A pattern is lit for the first `duty` part of every period, offset by
+//! its phase so neighbouring blades can chase or alternate. Square
+//! patterns are simply on in that window, breathing ones fade up and back
+//! down across it.
+
+use crate::math8::fraction8;
+
+/// On and off for this long each in the default pattern
+pub const BLINK_MSEC: u32 = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlinkShape {
+    /// Hard on and off
+    Square,
+    /// Fades up and down while on
+    Breathe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Blink {
+    pub period_ms: u32,
+    /// Part of the period the blade is lit, 255 for all of it
+    pub duty: u8,
+    /// Shifts the pattern later by this much
+    pub phase_ms: u32,
+    pub shape: BlinkShape,
+}
+
+impl Blink {
+    /// What `blink: true` gives, `BLINK_MSEC` on then off
+    pub const DEFAULT: Blink = Blink::square(2 * BLINK_MSEC, 128);
+
+    pub const fn square(period_ms: u32, duty: u8) -> Self {
+        Self {
+            period_ms,
+            duty,
+            phase_ms: 0,
+            shape: BlinkShape::Square,
+        }
+    }
+
+    /// Fades up and back down over the whole period
+    pub const fn breathe(period_ms: u32) -> Self {
+        Self {
+            period_ms,
+            duty: 255,
+            phase_ms: 0,
+            shape: BlinkShape::Breathe,
+        }
+    }
+
+    pub const fn with_duty(mut self, duty: u8) -> Self {
+        self.duty = duty;
+        self
+    }
+
+    pub const fn with_phase(mut self, phase_ms: u32) -> Self {
+        self.phase_ms = phase_ms;
+        self
+    }
+
+    /// How lit the blade is at `now_ms`, 255 fully
+    pub fn level(&self, now_ms: u32) -> u8 {
+        if self.period_ms == 0 {
+            return 255;
+        }
+
+        let period = self.period_ms as u64;
+        let elapsed = (now_ms as u64 + period - (self.phase_ms as u64 % period)) % period;
+        let on_ms = (period * self.duty as u64).div_ceil(255);
+        if elapsed >= on_ms {
+            return 0;
+        }
+
+        match self.shape {
+            BlinkShape::Square => 255,
+            BlinkShape::Breathe => {
+                let position = fraction8(elapsed as u32, on_ms as u32);
+                if position < 128 { position * 2 } else { (255 - position) * 2 }
+            }
+        }
+    }
+}
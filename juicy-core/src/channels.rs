@@ -0,0 +1,147 @@
+//! Several LED strips driven as one
+//!
+//! `MultiChannel` is itself a `SmartLedsWrite` sink: the LEDs written to it
+//! are handed to each channel in turn, so blade indices carry on
+//! seamlessly from one strip to the next.
+
+use smart_leds::SmartLedsWrite;
+
+/// One output strip, object safe so differently typed drivers can be mixed
+pub trait ChannelWrite {
+    /// `RGB8`, or `RGBW8` for RGBW strips
+    type Color;
+    /// Why the strip couldn't take a frame
+    type Error;
+
+    fn led_count(&self) -> usize;
+
+    /// Writes the next `led_count` LEDs from `leds`
+    fn write_leds(&mut self, leds: &mut dyn Iterator<Item = Self::Color>) -> Result<(), Self::Error>;
+}
+
+/// A `SmartLedsWrite` driver with a fixed number of LEDs
+pub struct Channel<S> {
+    sink: S,
+    led_count: usize,
+}
+
+impl<S> Channel<S>
+where
+    S: SmartLedsWrite,
+{
+    pub fn new(sink: S, led_count: usize) -> Self {
+        Self { sink, led_count }
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+}
+
+impl<S> ChannelWrite for Channel<S>
+where
+    S: SmartLedsWrite,
+{
+    type Color = S::Color;
+    type Error = S::Error;
+
+    fn led_count(&self) -> usize {
+        self.led_count
+    }
+
+    fn write_leds(&mut self, leds: &mut dyn Iterator<Item = S::Color>) -> Result<(), S::Error> {
+        // Unused channels aren't clocked at all
+        if self.led_count == 0 {
+            return Ok(());
+        }
+
+        // A strip that fails still uses up its LEDs, the next channel
+        // gets its own
+        let mut own = leds.take(self.led_count);
+        let written = self.sink.write(own.by_ref());
+        own.for_each(drop);
+        written
+    }
+}
+
+/// Failed write, `channel` is the index of the strip that failed and
+/// `cause` the strip's own error
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelError<E> {
+    pub channel: u8,
+    pub cause: E,
+}
+
+/// A tuple of channels driven as one strip
+pub struct MultiChannel<T> {
+    channels: T,
+}
+
+impl<T> MultiChannel<T> {
+    pub fn new(channels: T) -> Self {
+        Self { channels }
+    }
+
+    pub fn channels(&self) -> &T {
+        &self.channels
+    }
+
+    pub fn channels_mut(&mut self) -> &mut T {
+        &mut self.channels
+    }
+}
+
+macro_rules! multi_channel {
+    ($first:ident: $first_index:tt $(, $channel:ident: $index:tt)*) => {
+        impl<$first $(, $channel)*> MultiChannel<($first, $($channel,)*)>
+        where
+            $first: ChannelWrite,
+            $($channel: ChannelWrite<Color = $first::Color, Error = $first::Error>,)*
+        {
+            /// LEDs across every channel
+            pub fn led_count(&self) -> usize {
+                self.channels.$first_index.led_count() $(+ self.channels.$index.led_count())*
+            }
+        }
+
+        /// Every channel takes the same color type and fails the same way.
+        /// One failing strip doesn't hold up the others, every channel is
+        /// written and the first failure is returned.
+        impl<$first $(, $channel)*> SmartLedsWrite for MultiChannel<($first, $($channel,)*)>
+        where
+            $first: ChannelWrite,
+            $($channel: ChannelWrite<Color = $first::Color, Error = $first::Error>,)*
+        {
+            type Error = ChannelError<$first::Error>;
+            type Color = $first::Color;
+
+            fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+            where
+                T: Iterator<Item = I>,
+                I: Into<Self::Color>,
+            {
+                let mut leds = iterator.map(Into::into);
+                let written = self.channels.$first_index
+                    .write_leds(&mut leds)
+                    .map_err(|cause| ChannelError { channel: $first_index, cause });
+                $(
+                    let written = written.and(
+                        self.channels.$index
+                            .write_leds(&mut leds)
+                            .map_err(|cause| ChannelError { channel: $index, cause }),
+                    );
+                )*
+                written
+            }
+        }
+    };
+}
+
+multi_channel!(A: 0);
+multi_channel!(A: 0, B: 1);
+multi_channel!(A: 0, B: 1, C: 2);
+multi_channel!(A: 0, B: 1, C: 2, D: 3);
@@ -0,0 +1,9 @@
+use fugit::Instant;
+
+/// Millisecond time source used by the effects
+///
+/// The firmware implements this for its system timer, the simulator
+/// for a clock it can speed up or pause.
+pub trait Clock {
+    fn now(&self) -> Instant<u32, 1, 1000>;
+}
@@ -0,0 +1,315 @@
+//! Line oriented command console
+//!
+//! Commands arrive one per line over the USB serial port. Parsing and
+//! running them only needs an `EffectManager` and somewhere to write the
+//! reply, so the whole console is tested on the host.
+//!
+//! ```text
+//! effect <name>                    switch to an effect
+//! brightness <0-255>               master brightness
+//! duration <seconds>               how long the current effect plays
+//! params <delay_ms> [<param>]      restart the current effect with new timing
+//! status                           current effect and settings
+//! list                             registered effects
+//! blade <i> <r> <g> <b>            hold a blade at a color
+//! blade clear                      hand every blade back to the effects
+//! playlist                         show the playlist
+//! playlist clear                   empty the playlist
+//! playlist add <name> <seconds>    append an effect
+//! playlist order <order>           sequential, shuffle or random
+//! playlist play                    start from the first entry
+//! help                             this list
+//! ```
+//!
+//! The host CLI builds its lines with `Command`'s `Display`, so both ends
+//! share one definition of the protocol.
+
+use core::fmt::{self, Display, Formatter, Write};
+use core::num::NonZeroU32;
+use core::str::FromStr;
+
+use heapless::Vec;
+use smart_leds::RGB8;
+
+use crate::clock::Clock;
+use crate::effects::{find_effect, EffectManager, EFFECT_REGISTRY};
+use crate::playlist::{PlaylistEntry, PlaylistOrder};
+
+/// Longest command line accepted
+pub const MAX_LINE: usize = 64;
+
+/// USB vendor and product id the shell enumerates with
+pub const USB_VID: u16 = 0x16c0;
+pub const USB_PID: u16 = 0x27dd;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command<'a> {
+    Effect(&'a str),
+    Brightness(u8),
+    Duration(u32),
+    Params { delay_ms: u32, param: Option<u32> },
+    Status,
+    List,
+    Blade { blade: u8, color: RGB8 },
+    ClearBlades,
+    Playlist,
+    PlaylistClear,
+    PlaylistAdd { name: &'a str, duration_sec: u32 },
+    PlaylistOrder(PlaylistOrder),
+    PlaylistPlay,
+    Help,
+}
+
+/// Parses one line, surrounding and repeated whitespace is ignored
+pub fn parse(line: &str) -> Result<Command<'_>, &'static str> {
+    let mut words = line.split_whitespace();
+    let command = words.next().ok_or("empty line")?;
+
+    let parsed = match command {
+        "effect" => Command::Effect(words.next().ok_or("usage: effect <name>")?),
+        "brightness" => Command::Brightness(number(words.next(), "usage: brightness <0-255>")?),
+        "duration" => Command::Duration(seconds(words.next(), "usage: duration <seconds>")?),
+        "params" => {
+            let usage = "usage: params <delay_ms> [<param>]";
+            Command::Params {
+                delay_ms: number(words.next(), usage)?,
+                param: words.next().map(|word| number(Some(word), usage)).transpose()?,
+            }
+        }
+        "status" => Command::Status,
+        "list" => Command::List,
+        "blade" => match words.next() {
+            Some("clear") => Command::ClearBlades,
+            blade => {
+                let usage = "usage: blade <i> <r> <g> <b>";
+                Command::Blade {
+                    blade: number(blade, usage)?,
+                    color: RGB8::new(
+                        number(words.next(), usage)?,
+                        number(words.next(), usage)?,
+                        number(words.next(), usage)?,
+                    ),
+                }
+            }
+        },
+        "playlist" => match words.next() {
+            None => Command::Playlist,
+            Some("clear") => Command::PlaylistClear,
+            Some("add") => {
+                let usage = "usage: playlist add <name> <seconds>";
+                Command::PlaylistAdd {
+                    name: words.next().ok_or(usage)?,
+                    duration_sec: seconds(words.next(), usage)?,
+                }
+            }
+            Some("order") => {
+                let order = words.next().and_then(order_from_name);
+                Command::PlaylistOrder(order.ok_or("usage: playlist order <sequential|shuffle|random>")?)
+            }
+            Some("play") => Command::PlaylistPlay,
+            Some(_) => return Err("usage: playlist [clear|add|order|play]"),
+        },
+        "help" => Command::Help,
+        _ => return Err("unknown command, try help"),
+    };
+
+    if words.next().is_some() {
+        return Err("too many arguments");
+    }
+
+    Ok(parsed)
+}
+
+fn number<T: FromStr>(word: Option<&str>, usage: &'static str) -> Result<T, &'static str> {
+    word.and_then(|word| word.parse().ok()).ok_or(usage)
+}
+
+/// A duration, zero would switch effects on every frame
+fn seconds(word: Option<&str>, usage: &'static str) -> Result<u32, &'static str> {
+    number::<NonZeroU32>(word, usage).map(NonZeroU32::get)
+}
+
+fn order_name(order: PlaylistOrder) -> &'static str {
+    match order {
+        PlaylistOrder::Sequential => "sequential",
+        PlaylistOrder::Shuffle => "shuffle",
+        PlaylistOrder::WeightedRandom => "random",
+    }
+}
+
+fn order_from_name(name: &str) -> Option<PlaylistOrder> {
+    match name {
+        "sequential" => Some(PlaylistOrder::Sequential),
+        "shuffle" => Some(PlaylistOrder::Shuffle),
+        "random" => Some(PlaylistOrder::WeightedRandom),
+        _ => None,
+    }
+}
+
+/// Formats the command as the line `parse` reads back, without the newline
+impl Display for Command<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Command::Effect(name) => write!(f, "effect {}", name),
+            Command::Brightness(brightness) => write!(f, "brightness {}", brightness),
+            Command::Duration(duration_sec) => write!(f, "duration {}", duration_sec),
+            Command::Params { delay_ms, param: None } => write!(f, "params {}", delay_ms),
+            Command::Params { delay_ms, param: Some(param) } => write!(f, "params {} {}", delay_ms, param),
+            Command::Status => write!(f, "status"),
+            Command::List => write!(f, "list"),
+            Command::Blade { blade, color } => write!(f, "blade {} {} {} {}", blade, color.r, color.g, color.b),
+            Command::ClearBlades => write!(f, "blade clear"),
+            Command::Playlist => write!(f, "playlist"),
+            Command::PlaylistClear => write!(f, "playlist clear"),
+            Command::PlaylistAdd { name, duration_sec } => write!(f, "playlist add {} {}", name, duration_sec),
+            Command::PlaylistOrder(order) => write!(f, "playlist order {}", order_name(order)),
+            Command::PlaylistPlay => write!(f, "playlist play"),
+            Command::Help => write!(f, "help"),
+        }
+    }
+}
+
+/// Runs a command, writing its reply to `out` a line at a time
+pub fn execute(
+    command: Command<'_>,
+    manager: &mut EffectManager,
+    clock: &dyn Clock,
+    out: &mut dyn Write,
+) -> core::fmt::Result {
+    match command {
+        Command::Effect(name) => match find_effect(name) {
+            Some(effect_id) => {
+                let _ = manager.select_effect(effect_id, clock);
+                writeln!(out, "ok")
+            }
+            None => writeln!(out, "error: no effect called {}", name),
+        },
+        Command::Brightness(brightness) => {
+            manager.set_brightness(brightness);
+            writeln!(out, "ok")
+        }
+        Command::Duration(duration_sec) => {
+            manager.set_effect_duration(duration_sec);
+            writeln!(out, "ok")
+        }
+        Command::Params { delay_ms, param } => {
+            manager.set_params(delay_ms, param, clock);
+            writeln!(out, "ok")
+        }
+        Command::Status => {
+            writeln!(out, "effect: {}", manager.effect_name())?;
+            writeln!(out, "brightness: {}", manager.brightness())?;
+            writeln!(
+                out,
+                "duration: {}s ({}s elapsed)",
+                manager.effect_duration(),
+                manager.effect_elapsed(clock)
+            )
+        }
+        Command::List => {
+            for (index, info) in EFFECT_REGISTRY.iter().enumerate() {
+                writeln!(out, "{} {}", index, info.name)?;
+            }
+            Ok(())
+        }
+        Command::Blade { blade, color } => match manager.set_override(blade, Some(color)) {
+            Ok(()) => writeln!(out, "ok"),
+            Err(error) => writeln!(out, "error: {}", error),
+        },
+        Command::ClearBlades => {
+            manager.clear_overrides();
+            writeln!(out, "ok")
+        }
+        Command::Playlist => {
+            let playlist = manager.playlist();
+            writeln!(out, "order: {}", order_name(playlist.order()))?;
+            for (index, entry) in playlist.entries().iter().enumerate() {
+                writeln!(out, "{} {} {}s", index, EFFECT_REGISTRY[entry.effect_id].name, entry.duration_sec)?;
+            }
+            Ok(())
+        }
+        Command::PlaylistClear => {
+            manager.playlist_mut().clear();
+            writeln!(out, "ok")
+        }
+        Command::PlaylistAdd { name, duration_sec } => {
+            let Some(effect_id) = find_effect(name) else {
+                return writeln!(out, "error: no effect called {}", name);
+            };
+
+            let params = (EFFECT_REGISTRY[effect_id].default_params)();
+            let mut entry = PlaylistEntry::new(effect_id, params.brightness, params.delay_ms, duration_sec);
+            entry.param = params.param;
+            match manager.playlist_mut().push(entry) {
+                Ok(()) => writeln!(out, "ok"),
+                Err(error) => writeln!(out, "error: {}", error),
+            }
+        }
+        Command::PlaylistOrder(order) => {
+            manager.playlist_mut().set_order(order);
+            writeln!(out, "ok")
+        }
+        Command::PlaylistPlay => {
+            manager.restart_playlist(clock);
+            writeln!(out, "ok")
+        }
+        Command::Help => {
+            writeln!(out, "effect <name>, brightness <0-255>, duration <seconds>,")?;
+            writeln!(out, "params <delay_ms> [<param>], status, list, blade <i> <r> <g> <b>,")?;
+            writeln!(out, "blade clear, playlist [clear|add <name> <seconds>|order <order>|play], help")
+        }
+    }
+}
+
+/// Collects bytes from the serial port into lines
+pub struct LineBuffer {
+    line: Vec<u8, MAX_LINE>,
+    overflow: bool,
+    /// The line was handed out and is dropped on the next byte
+    complete: bool,
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            overflow: false,
+            complete: false,
+        }
+    }
+
+    /// Adds a byte, returning the finished line at a CR or LF. Lines that
+    /// are too long or not UTF-8 come back as an error.
+    pub fn push(&mut self, byte: u8) -> Option<Result<&str, &'static str>> {
+        if self.complete {
+            self.line.clear();
+            self.complete = false;
+        }
+
+        if byte != b'\r' && byte != b'\n' {
+            if self.line.push(byte).is_err() {
+                self.overflow = true;
+            }
+            return None;
+        }
+
+        if core::mem::take(&mut self.overflow) {
+            self.line.clear();
+            return Some(Err("line too long"));
+        }
+
+        // CR LF would otherwise give an empty second line
+        if self.line.is_empty() {
+            return None;
+        }
+
+        self.complete = true;
+        Some(core::str::from_utf8(&self.line).map_err(|_| "line is not UTF-8"))
+    }
+}
+
+impl Default for LineBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
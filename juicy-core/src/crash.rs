@@ -0,0 +1,140 @@
+//! Panic details kept across a reset
+//!
+//! The panic handler fills in a `CrashRecord` that lives in RAM the
+//! startup code leaves alone, then resets. On the next boot a valid
+//! record is reported and cleared. The CRC tells a real record from
+//! whatever the RAM held at power on.
+
+use core::fmt::{self, Display, Formatter, Write};
+
+use crate::crc::Crc32;
+
+/// Longest panic message kept, longer ones are cut short
+pub const MESSAGE_LEN: usize = 160;
+/// Longest source path kept, longer ones keep their end
+pub const FILE_LEN: usize = 64;
+
+const MAGIC: u32 = 0x4352_5348;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct CrashRecord {
+    magic: u32,
+    line: u32,
+    column: u32,
+    message_len: u32,
+    file_len: u32,
+    message: [u8; MESSAGE_LEN],
+    file: [u8; FILE_LEN],
+    crc: u32,
+}
+
+impl CrashRecord {
+    pub const fn new() -> Self {
+        Self {
+            magic: 0,
+            line: 0,
+            column: 0,
+            message_len: 0,
+            file_len: 0,
+            message: [0; MESSAGE_LEN],
+            file: [0; FILE_LEN],
+            crc: 0,
+        }
+    }
+
+    /// Fills in the record, it only has to survive until the reset
+    pub fn record(&mut self, message: &dyn Display, file: &str, line: u32, column: u32) {
+        let mut writer = Truncating { buffer: &mut self.message, len: 0 };
+        let _ = write!(writer, "{}", message);
+        self.message_len = writer.len as u32;
+
+        // The end of the path says more than the start
+        let mut start = file.len().saturating_sub(FILE_LEN);
+        while !file.is_char_boundary(start) {
+            start += 1;
+        }
+        let file = &file.as_bytes()[start..];
+        self.file[..file.len()].copy_from_slice(file);
+        self.file_len = file.len() as u32;
+
+        self.line = line;
+        self.column = column;
+        self.magic = MAGIC;
+        self.crc = self.checksum();
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.magic == MAGIC
+            && self.message_len as usize <= MESSAGE_LEN
+            && self.file_len as usize <= FILE_LEN
+            && self.crc == self.checksum()
+    }
+
+    pub fn clear(&mut self) {
+        self.magic = 0;
+    }
+
+    pub fn message(&self) -> &str {
+        text(&self.message, self.message_len)
+    }
+
+    pub fn file(&self) -> &str {
+        text(&self.file, self.file_len)
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    fn checksum(&self) -> u32 {
+        let mut crc = Crc32::new();
+        for word in [self.magic, self.line, self.column, self.message_len, self.file_len] {
+            crc.update(&word.to_le_bytes());
+        }
+        crc.update(&self.message[..(self.message_len as usize).min(MESSAGE_LEN)]);
+        crc.update(&self.file[..(self.file_len as usize).min(FILE_LEN)]);
+        crc.finish()
+    }
+}
+
+impl Default for CrashRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for CrashRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked at {}:{}:{}: {}", self.file(), self.line, self.column, self.message())
+    }
+}
+
+fn text(bytes: &[u8], len: u32) -> &str {
+    let bytes = &bytes[..(len as usize).min(bytes.len())];
+    core::str::from_utf8(bytes).unwrap_or("<garbled>")
+}
+
+/// Keeps as many whole characters as fit and drops the rest
+struct Truncating<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for Truncating<'_> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        for c in text.chars() {
+            let end = self.len + c.len_utf8();
+            if end > self.buffer.len() {
+                break;
+            }
+            c.encode_utf8(&mut self.buffer[self.len..end]);
+            self.len = end;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+//! CRC-32 as used by zip and ethernet, for records that outlive a reset
+
+/// Running CRC over data that arrives in pieces
+#[derive(Clone, Copy)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub const fn new() -> Self {
+        Self { crc: !0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.crc ^= byte as u32;
+            for _ in 0..8 {
+                self.crc = if self.crc & 1 != 0 { (self.crc >> 1) ^ 0xedb8_8320 } else { self.crc >> 1 };
+            }
+        }
+    }
+
+    pub fn finish(self) -> u32 {
+        !self.crc
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
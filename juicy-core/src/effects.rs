@@ -0,0 +1,758 @@
+use crate::clock::Clock;
+use crate::frame::{BladeSink, FrameBuffer, NUM_BLADES};
+use crate::math8::{blend, fraction8, lerp8, nscale8, scale8};
+use crate::pallet::{get_temperature, adjust_temperature, get_color_bright};
+use crate::playlist::{Playlist, PlaylistEntry};
+use crate::rng::{Rng, DEFAULT_SEED};
+use smart_leds::RGB8;
+use fugit::Instant;
+
+/// Parameters an effect is constructed with
+#[derive(Clone, Copy)]
+pub struct EffectParams {
+    pub brightness: u8,
+    pub delay_ms: u32,
+    /// Effect specific tuning value, `None` keeps the effect's default
+    pub param: Option<u32>,
+}
+
+/// Common interface for all visual effects
+///
+/// Adding a new effect means implementing this trait and adding a line
+/// to the `register_effects!` invocation below.
+pub trait Effect {
+    /// Unique name used to identify the effect
+    const NAME: &'static str;
+
+    /// Parameters used when the effect is built from the registry
+    fn default_params() -> EffectParams;
+
+    /// Builds the effect from a set of parameters, seeding any
+    /// randomness it uses from `seed`
+    fn from_params(params: &EffectParams, seed: u32) -> Self;
+
+    /// Returns the effect to the state it had when first constructed
+    fn reset(&mut self);
+
+    /// Advances the effect and renders its blades into the frame
+    ///
+    /// Returns true if any blade was written
+    fn update(&mut self, frame: &mut FrameBuffer, clock: &dyn Clock) -> bool;
+
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+}
+
+/// Registry entry describing how to build an effect
+pub struct EffectInfo {
+    pub name: &'static str,
+    pub default_params: fn() -> EffectParams,
+    pub build: fn(&EffectParams, u32) -> EffectSlot,
+}
+
+/// Generates `EffectSlot` (storage for any registered effect) and the
+/// `EFFECT_REGISTRY` table, in rotation order.
+macro_rules! register_effects {
+    ($($variant:ident => $effect:ty),+ $(,)?) => {
+        /// Storage for any registered effect
+        pub enum EffectSlot {
+            $($variant($effect),)+
+        }
+
+        impl EffectSlot {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(EffectSlot::$variant(effect) => effect.name(),)+
+                }
+            }
+
+            pub fn reset(&mut self) {
+                match self {
+                    $(EffectSlot::$variant(effect) => effect.reset(),)+
+                }
+            }
+
+            pub fn update(&mut self, frame: &mut FrameBuffer, clock: &dyn Clock) -> bool {
+                match self {
+                    $(EffectSlot::$variant(effect) => effect.update(frame, clock),)+
+                }
+            }
+        }
+
+        /// All known effects, in rotation order
+        pub static EFFECT_REGISTRY: &[EffectInfo] = &[
+            $(EffectInfo {
+                name: <$effect as Effect>::NAME,
+                default_params: <$effect as Effect>::default_params,
+                build: |params, seed| EffectSlot::$variant(<$effect as Effect>::from_params(params, seed)),
+            },)+
+        ];
+    };
+}
+
+register_effects! {
+    Fire => ShellFireEffect,
+    Spiral => ShellSpiralEffect,
+    SparkFire => ShellSparkFireEffect,
+}
+
+/// Looks up a registered effect by name
+pub fn find_effect(name: &str) -> Option<usize> {
+    EFFECT_REGISTRY.iter().position(|info| info.name == name)
+}
+
+/// Builds the effect described by a playlist entry
+fn build_effect(entry: &PlaylistEntry, seed: u32) -> EffectSlot {
+    (EFFECT_REGISTRY[entry.effect_id].build)(&entry.params(), seed)
+}
+
+/// How the manager moves from one effect to the next
+#[derive(Clone, Copy, PartialEq)]
+pub enum Transition {
+    /// Switch immediately
+    Cut,
+    /// Blend directly from the outgoing to the incoming effect
+    Crossfade,
+    /// Fade the outgoing effect to black, then fade in the incoming one
+    FadeThroughBlack,
+    /// Replace blades along the spiral, from the core outwards
+    Wipe,
+}
+
+/// Outgoing effect kept running while a transition is in progress
+struct Outgoing {
+    effect: EffectSlot,
+    frame: FrameBuffer,
+}
+
+pub struct EffectManager {
+    current_effect: EffectSlot,
+    current_frame: FrameBuffer,
+    outgoing: Option<Outgoing>,
+    playlist: Playlist,
+    rng: Rng,
+    effect_index: usize,
+    effect_duration_sec: u32,
+    effect_start_time: Instant<u32, 1, 1000>,
+    transition: Transition,
+    transition_ms: u32,
+    transition_progress: u8,
+    brightness: u8,
+    overrides: [Option<RGB8>; NUM_BLADES],
+    redraw: bool,
+    /// Effect picked by hand, until the playlist moves on
+    selected: Option<PlaylistEntry>,
+}
+
+impl EffectManager {
+    pub fn new(clock: &dyn Clock) -> Self {
+        Self::with_seed(clock, DEFAULT_SEED)
+    }
+
+    /// Creates a manager whose effects are all seeded from `seed`, eg a
+    /// value from the hardware random number generator
+    pub fn with_seed(clock: &dyn Clock, seed: u32) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut playlist = Playlist::default_rotation();
+        let entry = playlist.next(&mut rng).unwrap_or_else(|| {
+            let effect_id = find_effect(ShellSparkFireEffect::NAME).unwrap_or(0);
+            let params = ShellSparkFireEffect::default_params();
+            PlaylistEntry::new(effect_id, params.brightness, params.delay_ms, 60)
+        });
+
+        Self {
+            current_effect: build_effect(&entry, rng.next_u32()),
+            current_frame: FrameBuffer::new(),
+            outgoing: None,
+            playlist,
+            rng,
+            effect_index: entry.effect_id,
+            effect_duration_sec: entry.duration_sec,
+            effect_start_time: clock.now(),
+            transition: Transition::Crossfade,
+            transition_ms: 2000,
+            transition_progress: 0,
+            brightness: 255,
+            overrides: [None; NUM_BLADES],
+            redraw: false,
+            selected: None,
+        }
+    }
+
+    /// Selects the transition used between effects and how long it lasts
+    pub fn set_transition(&mut self, transition: Transition, transition_ms: u32) {
+        self.transition = transition;
+        self.transition_ms = transition_ms;
+    }
+
+    /// Replaces the playlist and starts playing it straight away
+    pub fn set_playlist(&mut self, playlist: Playlist, clock: &dyn Clock) {
+        self.playlist = playlist;
+        self.next_effect(clock);
+    }
+
+    pub fn update(&mut self, lights: &mut dyn BladeSink, clock: &dyn Clock) -> bool {
+        // Check if we should switch to the next effect
+        let elapsed = (clock.now() - self.effect_start_time).to_millis() / 1000;
+        if elapsed >= self.effect_duration_sec {
+            self.next_effect(clock);
+        }
+
+        // Run the current effect, and the outgoing one while transitioning
+        let mut updated = self.current_effect.update(&mut self.current_frame, clock);
+        if let Some(outgoing) = &mut self.outgoing {
+            updated |= outgoing.effect.update(&mut outgoing.frame, clock);
+        }
+
+        updated |= self.update_transition(clock);
+        updated |= core::mem::take(&mut self.redraw);
+
+        if updated {
+            self.show(lights);
+        }
+
+        updated
+    }
+
+    /// Moves on to the next playlist entry
+    pub fn next_effect(&mut self, clock: &dyn Clock) {
+        // An empty playlist keeps the current effect running
+        let Some(entry) = self.playlist.next(&mut self.rng) else {
+            self.effect_start_time = clock.now();
+            return;
+        };
+
+        self.selected = None;
+        self.play(entry, clock);
+    }
+
+    /// Switches to a registered effect with its default parameters
+    ///
+    /// The playlist resumes once the effect's duration has elapsed.
+    pub fn select_effect(&mut self, effect_id: usize, clock: &dyn Clock) -> Result<(), &'static str> {
+        let info = EFFECT_REGISTRY.get(effect_id).ok_or("effect id out of range")?;
+        let params = (info.default_params)();
+
+        let mut entry = PlaylistEntry::new(effect_id, params.brightness, params.delay_ms, self.effect_duration_sec);
+        entry.param = params.param;
+        self.select_entry(entry, clock);
+
+        Ok(())
+    }
+
+    /// Plays an entry picked by hand, eg restored from saved settings
+    ///
+    /// The playlist resumes once the entry's duration has elapsed.
+    pub fn select_entry(&mut self, entry: PlaylistEntry, clock: &dyn Clock) {
+        self.selected = Some(entry);
+        self.play(entry, clock);
+    }
+
+    /// The effect picked by hand, `None` while the playlist is in charge
+    pub fn selected(&self) -> Option<&PlaylistEntry> {
+        self.selected.as_ref()
+    }
+
+    /// Restarts the current effect with new timing and tuning values,
+    /// `None` keeps the effect's default param
+    pub fn set_params(&mut self, delay_ms: u32, param: Option<u32>, clock: &dyn Clock) {
+        let params = (EFFECT_REGISTRY[self.effect_index].default_params)();
+        let mut entry = PlaylistEntry::new(self.effect_index, params.brightness, delay_ms, self.effect_duration_sec);
+        entry.param = param.or(params.param);
+        self.select_entry(entry, clock);
+    }
+
+    pub fn playlist(&self) -> &Playlist {
+        &self.playlist
+    }
+
+    /// Edits take effect as the playlist moves on, see `restart_playlist`
+    pub fn playlist_mut(&mut self) -> &mut Playlist {
+        &mut self.playlist
+    }
+
+    /// Plays the playlist from its first entry
+    pub fn restart_playlist(&mut self, clock: &dyn Clock) {
+        self.playlist.rewind();
+        self.next_effect(clock);
+    }
+
+    pub fn effect_index(&self) -> usize {
+        self.effect_index
+    }
+
+    /// Master brightness over every effect, 255 leaves them unchanged
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.redraw = true;
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// How long the current effect plays before the playlist moves on
+    pub fn set_effect_duration(&mut self, duration_sec: u32) {
+        self.effect_duration_sec = duration_sec;
+        if let Some(selected) = &mut self.selected {
+            selected.duration_sec = duration_sec;
+        }
+    }
+
+    pub fn effect_duration(&self) -> u32 {
+        self.effect_duration_sec
+    }
+
+    /// Seconds the current effect has been playing
+    pub fn effect_elapsed(&self, clock: &dyn Clock) -> u32 {
+        (clock.now() - self.effect_start_time).to_millis() / 1000
+    }
+
+    /// Holds a blade at a fixed color over whatever the effects draw,
+    /// `None` hands it back to the effects
+    pub fn set_override(&mut self, blade: u8, color: Option<RGB8>) -> Result<(), &'static str> {
+        let slot = self.overrides.get_mut(blade as usize).ok_or("blade index out of range")?;
+        *slot = color;
+        self.redraw = true;
+
+        Ok(())
+    }
+
+    pub fn clear_overrides(&mut self) {
+        self.overrides = [None; NUM_BLADES];
+        self.redraw = true;
+    }
+
+    pub fn effect_name(&self) -> &'static str {
+        self.current_effect.name()
+    }
+
+    /// Starts an entry, transitioning from whatever is currently playing
+    pub fn play(&mut self, entry: PlaylistEntry, clock: &dyn Clock) {
+        self.effect_index = entry.effect_id;
+        self.effect_duration_sec = entry.duration_sec;
+
+        let incoming = build_effect(&entry, self.rng.next_u32());
+        let previous_effect = core::mem::replace(&mut self.current_effect, incoming);
+        let previous_frame = core::mem::take(&mut self.current_frame);
+
+        self.outgoing = if self.transition == Transition::Cut || self.transition_ms == 0 {
+            None
+        } else {
+            Some(Outgoing { effect: previous_effect, frame: previous_frame })
+        };
+        self.transition_progress = 0;
+        self.effect_start_time = clock.now();
+    }
+
+    /// Finishes any transition at once, eg when restoring the effect at boot
+    pub fn skip_transition(&mut self) {
+        if self.outgoing.take().is_some() {
+            self.redraw = true;
+        }
+    }
+
+    /// Advances the transition, returns true if the blend changed
+    fn update_transition(&mut self, clock: &dyn Clock) -> bool {
+        if self.outgoing.is_none() {
+            return false;
+        }
+
+        let elapsed = (clock.now() - self.effect_start_time).to_millis();
+        if elapsed >= self.transition_ms {
+            self.outgoing = None;
+            return true;
+        }
+
+        let progress = ((elapsed as u64 * 255) / self.transition_ms as u64) as u8;
+        if progress == self.transition_progress {
+            return false;
+        }
+
+        self.transition_progress = progress;
+        true
+    }
+
+    /// Blends the active frames and hands the result to the lights
+    fn show(&self, lights: &mut dyn BladeSink) {
+        let progress = self.transition_progress;
+
+        for blade in 0..NUM_BLADES {
+            let incoming = self.current_frame.get_blade(blade);
+            let incoming_white = self.current_frame.get_white(blade);
+
+            let (color, white) = match &self.outgoing {
+                None => (incoming, incoming_white),
+                Some(outgoing) => {
+                    let outgoing_white = outgoing.frame.get_white(blade);
+                    let outgoing = outgoing.frame.get_blade(blade);
+                    match self.transition {
+                        Transition::Cut => (incoming, incoming_white),
+                        Transition::Crossfade => (
+                            blend(outgoing, incoming, progress),
+                            lerp8(outgoing_white, incoming_white, progress),
+                        ),
+                        Transition::FadeThroughBlack => {
+                            if progress < 128 {
+                                let scale = 255 - progress * 2;
+                                (nscale8(outgoing, scale), scale8(outgoing_white, scale))
+                            } else {
+                                let scale = (progress - 128) * 2;
+                                (nscale8(incoming, scale), scale8(incoming_white, scale))
+                            }
+                        }
+                        Transition::Wipe => {
+                            let edge = (progress as usize * NUM_BLADES) / 255;
+                            if blade < edge {
+                                (incoming, incoming_white)
+                            } else {
+                                (outgoing, outgoing_white)
+                            }
+                        }
+                    }
+                }
+            };
+
+            // Blinking follows whichever frame is mostly showing
+            let blink = match &self.outgoing {
+                Some(outgoing) => {
+                    let outgoing_shown = match self.transition {
+                        Transition::Cut => false,
+                        Transition::Wipe => blade >= (progress as usize * NUM_BLADES) / 255,
+                        Transition::Crossfade | Transition::FadeThroughBlack => progress < 128,
+                    };
+                    if outgoing_shown {
+                        outgoing.frame.get_blink(blade)
+                    } else {
+                        self.current_frame.get_blink(blade)
+                    }
+                }
+                None => self.current_frame.get_blink(blade),
+            };
+
+            let (color, white, blink) = match self.overrides[blade] {
+                Some(color) => (color, 0, None),
+                None => (nscale8(color, self.brightness), scale8(white, self.brightness), blink),
+            };
+
+            let _ = lights.set_blade(blade as u8, color, false);
+            let _ = lights.set_white(blade as u8, white);
+            let _ = lights.set_blink(blade as u8, blink);
+        }
+    }
+}
+
+// Shell Spark Fire Effect
+pub struct ShellSparkFireEffect {
+    temperatures: [u8; NUM_BLADES],
+    brightness: u8,
+    delay_ms: u32,
+    last_update: Instant<u32, 1, 1000>,
+    spark_odds: u32,
+    seed: u32,
+    rng: Rng,
+}
+
+impl ShellSparkFireEffect {
+    pub fn new(brightness: u8, delay_ms: u32) -> Self {
+        let temps = [0u8; NUM_BLADES];
+
+        Self {
+            temperatures: temps,
+            brightness,
+            delay_ms,
+            last_update: Instant::<u32, 1, 1000>::from_ticks(0),
+            spark_odds: 30,
+            seed: DEFAULT_SEED,
+            rng: Rng::new(DEFAULT_SEED),
+        }
+    }
+}
+
+impl Effect for ShellSparkFireEffect {
+    const NAME: &'static str = "spark_fire";
+
+    fn default_params() -> EffectParams {
+        EffectParams { brightness: 100, delay_ms: 50, param: None }
+    }
+
+    /// `param` sets the spark odds, 1 in N updates starts a spark
+    fn from_params(params: &EffectParams, seed: u32) -> Self {
+        let mut effect = Self::new(params.brightness, params.delay_ms);
+        if let Some(odds) = params.param {
+            effect.spark_odds = odds.max(1);
+        }
+        effect.seed = seed;
+        effect.rng = Rng::new(seed);
+        effect
+    }
+
+    fn reset(&mut self) {
+        let params = EffectParams { brightness: self.brightness, delay_ms: self.delay_ms, param: Some(self.spark_odds) };
+        *self = Self::from_params(&params, self.seed);
+    }
+
+    fn update(&mut self, frame: &mut FrameBuffer, clock: &dyn Clock) -> bool {
+        let now = clock.now();
+
+        if (now - self.last_update).to_millis() < self.delay_ms {
+            return false;
+        }
+
+        self.last_update = now;
+
+        // Random spark at position 0
+        if self.rng.one_in(self.spark_odds) {
+            let tint = self.rng.range_between(1, 7) as u8;
+            self.temperatures[0] = get_temperature(tint, 15);
+        }
+
+        // Output colors for all blades (1-indexed in Arduino, 0-indexed here)
+        for blade in 0..NUM_BLADES {
+            let color = get_color_bright(self.temperatures[blade], self.brightness);
+            let _ = frame.set_blade(blade as u8, color);
+        }
+
+        // Animate colors - shift down and decay
+        for blade in (1..NUM_BLADES).rev() {
+            self.temperatures[blade] = self.temperatures[blade - 1];
+        }
+        self.temperatures[0] = adjust_temperature(self.temperatures[0], -1);
+
+        true
+    }
+}
+
+// Shell Fire Effect (different from spark fire - uses temperature gradient)
+pub struct ShellFireEffect {
+    temperatures: [u8; NUM_BLADES],
+    brightness: u8,
+    delay_ms: u32,
+    last_update: Instant<u32, 1, 1000>,
+    fire_spark_odds: u32,
+    seed: u32,
+    rng: Rng,
+}
+
+impl ShellFireEffect {
+    pub fn new(brightness: u8, delay_ms: u32) -> Self {
+        // Initialize with hot core at lower blades
+        let mut temps = [0u8; NUM_BLADES];
+        // Set initial base heat in lower blades
+        for (i, temp) in temps.iter_mut().take(10).enumerate() {
+            *temp = 6 - (i / 2) as u8;
+        }
+
+        Self {
+            temperatures: temps,
+            brightness,
+            delay_ms,
+            last_update: Instant::<u32, 1, 1000>::from_ticks(0),
+            fire_spark_odds: 8,
+            seed: 0xDEADBEEF,
+            rng: Rng::new(0xDEADBEEF),
+        }
+    }
+
+    fn get_rand_temperature_color(&self, temperature: u8, major_flicker: u8, minor_flicker: u8) -> RGB8 {
+        // Return black for temperature 0
+        if temperature == 0 {
+            return RGB8::new(0, 0, 0);
+        }
+
+        let flicker = scale8(scale8(self.brightness, minor_flicker), major_flicker);
+
+        // Channel levels out of 255, from deep red embers to yellow-white
+        let (r_level, g_level, b_level) = match temperature {
+            1 => (255, 26, 0),
+            2 => (255, 51, 0),
+            3 => (255, 77, 0),
+            4 => (255, 102, 0),
+            5 => (255, 128, 0),
+            6 => (255, 153, 13),
+            7 => (255, 179, 26),
+            8 => (255, 204, 38),
+            9 => (255, 230, 51),
+            _ => (255, 255, 64),
+        };
+
+        RGB8::new(
+            scale8(flicker, r_level),
+            scale8(flicker, g_level),
+            scale8(flicker, b_level),
+        )
+    }
+}
+
+impl Effect for ShellFireEffect {
+    const NAME: &'static str = "fire";
+
+    fn default_params() -> EffectParams {
+        EffectParams { brightness: 120, delay_ms: 60, param: None }
+    }
+
+    /// `param` sets the spark odds, 1 in N updates starts a spark
+    fn from_params(params: &EffectParams, seed: u32) -> Self {
+        let mut effect = Self::new(params.brightness, params.delay_ms);
+        if let Some(odds) = params.param {
+            effect.fire_spark_odds = odds.max(1);
+        }
+        effect.seed = seed;
+        effect.rng = Rng::new(seed);
+        effect
+    }
+
+    fn reset(&mut self) {
+        let params = EffectParams { brightness: self.brightness, delay_ms: self.delay_ms, param: Some(self.fire_spark_odds) };
+        *self = Self::from_params(&params, self.seed);
+    }
+
+    fn update(&mut self, frame: &mut FrameBuffer, clock: &dyn Clock) -> bool {
+        let now = clock.now();
+
+        if (now - self.last_update).to_millis() < self.delay_ms {
+            return false;
+        }
+
+        self.last_update = now;
+
+        // Animate - heat rises and diminishes as it goes up
+        // Shift temperatures upward (from low blade numbers to high)
+        // Process from high to low to avoid overwriting
+        for blade in (1..NUM_BLADES).rev() {
+            // Temperature from below - only decay every 3 blades so sparks travel further
+            if self.temperatures[blade - 1] > 0 {
+                // Decay based on blade position (every 3rd blade loses 1 temperature)
+                let decay = if blade % 3 == 0 { 1 } else { 0 };
+                self.temperatures[blade] = self.temperatures[blade - 1].saturating_sub(decay);
+            } else {
+                // Natural cooling for blades with no heat from below
+                self.temperatures[blade] = self.temperatures[blade].saturating_sub(1);
+            }
+        }
+
+        // Random spark - can occur anywhere in first third of blades (hot core)
+        if self.rng.one_in(self.fire_spark_odds) {
+            let spark_pos = self.rng.range((NUM_BLADES / 3) as u32) as usize;
+            let spark_temp = self.rng.range_between(8, 13) as u8;  // Higher starting temp (8-12)
+            self.temperatures[spark_pos] = spark_temp;
+        }
+
+        // Keep the core hot - always maintain some heat at the base
+        if self.rng.one_in(3) {
+            let base_pos = self.rng.range(5) as usize;
+            if self.temperatures[base_pos] < 5 {
+                self.temperatures[base_pos] = self.rng.range_between(5, 8) as u8;
+            }
+        }
+
+        // Flicker the fire
+        for blade in 0..NUM_BLADES {
+            let major_flicker = fraction8(self.rng.range(20), 20);
+            let minor_flicker = fraction8(self.rng.range(20), 20);
+            let color = self.get_rand_temperature_color(
+                self.temperatures[blade],
+                major_flicker,
+                minor_flicker,
+            );
+            let _ = frame.set_blade(blade as u8, color);
+        }
+
+        true
+    }
+}
+
+// Shell Spiral Effect
+pub struct ShellSpiralEffect {
+    spiral_index: usize,
+    brightness: u8,
+    delay_ms: u32,
+    last_update: Instant<u32, 1, 1000>,
+    cur_color: usize,
+    cur_band_cnt: usize,
+    color_band_size: usize,
+    last_color: Option<RGB8>,
+}
+
+impl ShellSpiralEffect {
+    pub fn new(brightness: u8, delay_ms: u32) -> Self {
+        Self {
+            spiral_index: 0,
+            brightness,
+            delay_ms,
+            last_update: Instant::<u32, 1, 1000>::from_ticks(0),
+            cur_color: 0,
+            cur_band_cnt: 0,
+            color_band_size: NUM_BLADES,  // One full cycle before changing color
+            last_color: None,
+        }
+    }
+
+    fn get_next_color(&mut self) -> RGB8 {
+        let b = self.brightness;
+        let color = match self.cur_color {
+            0 => RGB8::new(b, 0, 0),
+            1 => RGB8::new(0, b, 0),
+            2 => RGB8::new(0, 0, b),
+            3 => RGB8::new(b, 0, b),
+            4 => RGB8::new(b, b, 0),
+            5 => RGB8::new(0, b, b),
+            _ => RGB8::new(0, 0, b),
+        };
+
+        // After returning current color, increment and check if we should change for next time
+        self.cur_band_cnt += 1;
+        if self.cur_band_cnt >= self.color_band_size {
+            self.cur_color = (self.cur_color + 1) % 6;
+            self.cur_band_cnt = 0;
+        }
+
+        color
+    }
+}
+
+impl Effect for ShellSpiralEffect {
+    const NAME: &'static str = "spiral";
+
+    fn default_params() -> EffectParams {
+        EffectParams { brightness: 80, delay_ms: 50, param: None }
+    }
+
+    /// `param` sets how many blades are drawn before the color changes
+    fn from_params(params: &EffectParams, _seed: u32) -> Self {
+        let mut effect = Self::new(params.brightness, params.delay_ms);
+        if let Some(band_size) = params.param {
+            effect.color_band_size = (band_size as usize).max(1);
+        }
+        effect
+    }
+
+    fn reset(&mut self) {
+        let color_band_size = self.color_band_size;
+        *self = Self::new(self.brightness, self.delay_ms);
+        self.color_band_size = color_band_size;
+    }
+
+    fn update(&mut self, frame: &mut FrameBuffer, clock: &dyn Clock) -> bool {
+        let now = clock.now();
+
+        if (now - self.last_update).to_millis() < self.delay_ms {
+            return false;
+        }
+
+        self.last_update = now;
+
+        let this_color = self.get_next_color();
+
+        // Set current blade to color
+        let _ = frame.set_blade(self.spiral_index as u8, this_color);
+
+        // Move to next blade
+        self.spiral_index = (self.spiral_index + 1) % NUM_BLADES;
+
+        self.last_color = Some(this_color);
+
+        true
+    }
+}
@@ -0,0 +1,86 @@
+//! Fault counting for output that can fail and be retried
+//!
+//! Every failure is counted and retried on the next pass. After too many
+//! in a row the caller is told to re-initialize the hardware, which then
+//! gets a fresh run of retries.
+
+use core::fmt::{self, Debug, Display, Formatter};
+
+/// What to do after a failure
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Recovery {
+    /// Try again on the next pass
+    Retry,
+    /// Set the hardware up again before retrying
+    Reinit,
+}
+
+/// Failure counts kept for diagnostics
+#[derive(Clone, Copy, Debug)]
+pub struct FaultCounter<E> {
+    reinit_after: u32,
+    errors: u32,
+    consecutive: u32,
+    reinits: u32,
+    last_error: Option<E>,
+}
+
+impl<E: Copy> FaultCounter<E> {
+    /// Asks for a re-init after `reinit_after` failures in a row
+    pub const fn new(reinit_after: u32) -> Self {
+        Self {
+            reinit_after,
+            errors: 0,
+            consecutive: 0,
+            reinits: 0,
+            last_error: None,
+        }
+    }
+
+    pub fn record_ok(&mut self) {
+        self.consecutive = 0;
+    }
+
+    pub fn record_error(&mut self, error: E) -> Recovery {
+        self.errors = self.errors.wrapping_add(1);
+        self.consecutive += 1;
+        self.last_error = Some(error);
+
+        if self.consecutive < self.reinit_after {
+            return Recovery::Retry;
+        }
+
+        self.consecutive = 0;
+        self.reinits = self.reinits.wrapping_add(1);
+        Recovery::Reinit
+    }
+
+    /// Failures since start up
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Failures since the last success or re-init
+    pub fn consecutive(&self) -> u32 {
+        self.consecutive
+    }
+
+    pub fn reinits(&self) -> u32 {
+        self.reinits
+    }
+
+    pub fn last_error(&self) -> Option<E> {
+        self.last_error
+    }
+}
+
+/// One line summary, eg `3 errors, 1 restarts, last Timeout`
+impl<E: Debug> Display for FaultCounter<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} errors, {} restarts", self.errors, self.reinits)?;
+        match &self.last_error {
+            Some(error) => write!(f, ", last {:?}", error),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use smart_leds::RGB8;
+
+use crate::blink::Blink;
+
+pub const NUM_BLADES: usize = 32;
+
+/// Off-screen copy of every blade's color
+///
+/// Effects render into a frame buffer rather than directly into
+/// `LightPorts` so that the effect manager can combine frames before
+/// they are displayed. Each blade also has a white level, shown by the
+/// white LED on RGBW strips and mixed into the color on RGB ones, and
+/// can be given a blink pattern.
+#[derive(Clone)]
+pub struct FrameBuffer {
+    blades: [RGB8; NUM_BLADES],
+    whites: [u8; NUM_BLADES],
+    blinks: [Option<Blink>; NUM_BLADES],
+}
+
+impl FrameBuffer {
+    pub fn new() -> Self {
+        Self {
+            blades: [RGB8::default(); NUM_BLADES],
+            whites: [0; NUM_BLADES],
+            blinks: [None; NUM_BLADES],
+        }
+    }
+
+    pub fn set_blade(&mut self, blade: u8, color: RGB8) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.blades[blade] = color;
+
+        Ok(())
+    }
+
+    pub fn get_blade(&self, blade: usize) -> RGB8 {
+        self.blades[blade]
+    }
+
+    pub fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.whites[blade] = white;
+
+        Ok(())
+    }
+
+    pub fn get_white(&self, blade: usize) -> u8 {
+        self.whites[blade]
+    }
+
+    pub fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.blinks[blade] = blink;
+
+        Ok(())
+    }
+
+    pub fn get_blink(&self, blade: usize) -> Option<Blink> {
+        self.blinks[blade]
+    }
+
+    pub fn clear(&mut self) {
+        self.blades = [RGB8::default(); NUM_BLADES];
+        self.whites = [0; NUM_BLADES];
+        self.blinks = [None; NUM_BLADES];
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Destination for finished frames, eg `LightPorts`
+pub trait BladeSink {
+    fn set_blade(&mut self, blade: u8, color: RGB8, blink: bool) -> Result<(), &'static str>;
+
+    /// White set directly by an effect, sinks without white can ignore it
+    fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        let _ = (blade, white);
+        Ok(())
+    }
+
+    /// Blink pattern, replacing the plain `blink` flag of `set_blade`
+    fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        let _ = (blade, blink);
+        Ok(())
+    }
+}
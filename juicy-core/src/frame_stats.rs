@@ -0,0 +1,93 @@
+//! Frame timing statistics
+//!
+//! The firmware renders one frame per scheduler tick. Each frame's busy
+//! time is recorded here in whatever unit the caller measures (CPU cycles
+//! on the board) against the time one tick allows.
+
+/// Timing since the last `reset_window`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameWindow {
+    pub frames: u32,
+    pub average: u32,
+    pub max: u32,
+    /// Share of the frame budget in use, in percent
+    pub load_percent: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    budget: u32,
+    frames: u32,
+    overruns: u32,
+    missed: u32,
+    window_frames: u32,
+    window_busy: u64,
+    window_max: u32,
+}
+
+impl FrameStats {
+    /// `budget` is the time one frame tick allows
+    pub const fn new(budget: u32) -> Self {
+        Self {
+            budget,
+            frames: 0,
+            overruns: 0,
+            missed: 0,
+            window_frames: 0,
+            window_busy: 0,
+            window_max: 0,
+        }
+    }
+
+    /// Records a frame that kept the CPU busy for `busy`
+    pub fn record(&mut self, busy: u32) {
+        self.frames = self.frames.wrapping_add(1);
+        if busy > self.budget {
+            self.overruns = self.overruns.wrapping_add(1);
+        }
+
+        self.window_frames += 1;
+        self.window_busy += busy as u64;
+        self.window_max = self.window_max.max(busy);
+    }
+
+    /// Records ticks that went by without a frame
+    pub fn record_missed(&mut self, ticks: u32) {
+        self.missed = self.missed.wrapping_add(ticks);
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Frames that took longer than the budget
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+
+    /// Ticks skipped because a frame overran
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+
+    pub fn window(&self) -> FrameWindow {
+        if self.window_frames == 0 {
+            return FrameWindow::default();
+        }
+
+        let average = (self.window_busy / self.window_frames as u64) as u32;
+        FrameWindow {
+            frames: self.window_frames,
+            average,
+            max: self.window_max,
+            load_percent: ((average as u64 * 100) / self.budget.max(1) as u64) as u32,
+        }
+    }
+
+    /// Starts a new window, the totals carry on
+    pub fn reset_window(&mut self) {
+        self.window_frames = 0;
+        self.window_busy = 0;
+        self.window_max = 0;
+    }
+}
@@ -0,0 +1,96 @@
+//! Physical layout of the blades
+//!
+//! The blades wind around the shell in a rising spiral, blade 0 at the
+//! core. This gives each blade coordinates so effects can work in terms of
+//! "up the shell" or "around the shell" instead of index arithmetic.
+//! Coordinates are `u8` fractions, matching `math8`.
+
+use crate::frame::NUM_BLADES;
+
+pub const DEFAULT_BLADES_PER_TURN: u8 = 8;
+
+/// Where a blade sits on the shell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BladePosition {
+    /// 0 at the core up to 255 at the top blade
+    pub height: u8,
+    /// Position around the shell, 256 steps per revolution
+    pub angle: u8,
+    /// Which turn of the spiral the blade is on, 0 at the core
+    pub ring: u8,
+}
+
+pub struct ShellGeometry {
+    blades_per_turn: u8,
+    positions: [BladePosition; NUM_BLADES],
+}
+
+impl ShellGeometry {
+    /// Lays out the blades with `blades_per_turn` blades to each
+    /// revolution of the spiral
+    pub const fn new(blades_per_turn: u8) -> Self {
+        let blades_per_turn = if blades_per_turn == 0 { 1 } else { blades_per_turn };
+        let mut positions = [BladePosition { height: 0, angle: 0, ring: 0 }; NUM_BLADES];
+
+        let mut blade = 0;
+        while blade < NUM_BLADES {
+            let turn = blades_per_turn as usize;
+            positions[blade] = BladePosition {
+                height: if NUM_BLADES > 1 { (blade * 255 / (NUM_BLADES - 1)) as u8 } else { 0 },
+                angle: ((blade % turn) * 256 / turn) as u8,
+                ring: (blade / turn) as u8,
+            };
+            blade += 1;
+        }
+
+        Self { blades_per_turn, positions }
+    }
+
+    pub fn blades_per_turn(&self) -> u8 {
+        self.blades_per_turn
+    }
+
+    /// Number of turns in the spiral, counting a partial top turn
+    pub fn rings(&self) -> u8 {
+        NUM_BLADES.div_ceil(self.blades_per_turn as usize) as u8
+    }
+
+    pub fn position(&self, blade: usize) -> BladePosition {
+        self.positions[blade]
+    }
+
+    pub fn positions(&self) -> &[BladePosition; NUM_BLADES] {
+        &self.positions
+    }
+
+    /// Blades whose height lies within `half_width` of `center`, eg a
+    /// horizontal band around the shell
+    pub fn blades_in_band(&self, center: u8, half_width: u8) -> impl Iterator<Item = usize> + '_ {
+        self.positions
+            .iter()
+            .enumerate()
+            .filter(move |(_, position)| position.height.abs_diff(center) <= half_width)
+            .map(|(blade, _)| blade)
+    }
+
+    /// Blades on one turn of the spiral
+    pub fn blades_in_ring(&self, ring: u8) -> impl Iterator<Item = usize> + '_ {
+        self.positions
+            .iter()
+            .enumerate()
+            .filter(move |(_, position)| position.ring == ring)
+            .map(|(blade, _)| blade)
+    }
+}
+
+impl Default for ShellGeometry {
+    fn default() -> Self {
+        Self::new(DEFAULT_BLADES_PER_TURN)
+    }
+}
+
+/// Shortest distance between two angles, 0 to 128 (half a turn)
+pub fn angle_distance(a: u8, b: u8) -> u8 {
+    let diff = a.wrapping_sub(b);
+    diff.min(diff.wrapping_neg())
+}
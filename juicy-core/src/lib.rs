@@ -0,0 +1,32 @@
+//! Hardware independent rendering core for the shell
+//!
+//! Everything here works against the `Clock` trait and a
+//! `SmartLedsWrite` sink, so it runs unchanged on the board, in the
+//! simulator and under `cargo test` on the host.
+
+#![no_std]
+
+pub mod apa102;
+pub mod bitstream;
+pub mod blink;
+pub mod channels;
+pub mod clock;
+pub mod console;
+pub mod crash;
+pub mod crc;
+pub mod effects;
+pub mod faults;
+pub mod frame;
+pub mod frame_stats;
+pub mod geometry;
+pub mod lights;
+pub mod math8;
+pub mod output;
+pub mod pallet;
+pub mod playlist;
+pub mod power;
+pub mod rng;
+pub mod sections;
+pub mod settings;
+pub mod stream;
+pub mod ws2812;
@@ -0,0 +1,223 @@
+use fugit::Instant;
+use smart_leds::{SmartLedsWrite, RGB8};
+
+pub use crate::blink::BLINK_MSEC;
+
+use crate::blink::Blink;
+use crate::clock::Clock;
+use crate::frame::{BladeSink, NUM_BLADES};
+use crate::math8::{nscale8, scale8};
+use crate::output::{DitherError, OutputStage, Pixel};
+use crate::power::{PowerBudget, PowerReport};
+use crate::sections::{SectionMap, MAX_LEDS, ONE_PER_BLADE};
+
+/// Blade colors and blinking, written out to any `SmartLedsWrite` sink
+///
+/// Each blade fills every LED of its section in the `SectionMap`. The
+/// sink can take `RGB8` or, for RGBW strips, `RGBW8`. Every blade can
+/// have its own `Blink` pattern, timed from when the lights were created.
+pub struct BladeLights<S> {
+    blade_data: [RGB8; NUM_BLADES],
+    blade_white: [u8; NUM_BLADES],
+    blink: [Option<Blink>; NUM_BLADES],
+    /// Blink levels of the last frame written, `None` before the first
+    blink_levels: Option<[u8; NUM_BLADES]>,
+    sections: &'static SectionMap,
+    output: OutputStage,
+    power_budget: Option<PowerBudget>,
+    power: PowerReport,
+    dithering: bool,
+    dither_error: [DitherError; MAX_LEDS],
+    sink: S,
+    epoch: Instant<u32, 1, 1000>,
+}
+
+impl<S> BladeLights<S>
+where
+    S: SmartLedsWrite,
+    S::Color: Pixel,
+{
+    /// One LED per blade
+    pub fn new(sink: S, clock: &dyn Clock) -> Self {
+        Self::with_sections(sink, &ONE_PER_BLADE, clock)
+    }
+
+    pub fn with_sections(sink: S, sections: &'static SectionMap, clock: &dyn Clock) -> Self {
+        Self {
+            blade_data: [RGB8::default(); NUM_BLADES],
+            blade_white: [0; NUM_BLADES],
+            blink: [None; NUM_BLADES],
+            blink_levels: None,
+            sections,
+            output: OutputStage::new(),
+            power_budget: None,
+            power: PowerReport { draw_ma: 0, scale: 255, limited_frames: 0 },
+            dithering: false,
+            dither_error: [[0; 4]; MAX_LEDS],
+            sink,
+            epoch: clock.now(),
+        }
+    }
+
+    /// `blink` gives the blade the default pattern, or clears any pattern
+    pub fn set_blade(&mut self, blade: u8, color: RGB8, blink: bool) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.blade_data[blade] = color;
+        self.blink[blade] = if blink { Some(Blink::DEFAULT) } else { None };
+
+        Ok(())
+    }
+
+    /// Blink pattern for a blade, `None` to keep it steady
+    pub fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.blink[blade] = blink;
+
+        Ok(())
+    }
+
+    /// White level for a blade, on top of any white pulled out of its color
+    pub fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.blade_white[blade] = white;
+
+        Ok(())
+    }
+
+    /// Gamma, white balance and color order applied just before writing
+    pub fn set_output(&mut self, output: OutputStage) {
+        self.output = output;
+    }
+
+    pub fn output(&self) -> &OutputStage {
+        &self.output
+    }
+
+    /// Temporal dithering, so dim fades step through levels finer than
+    /// 8 bits after gamma. While on, every refresh writes a frame.
+    pub fn set_dithering(&mut self, dithering: bool) {
+        self.dithering = dithering;
+        self.dither_error = [[0; 4]; MAX_LEDS];
+    }
+
+    pub fn dithering(&self) -> bool {
+        self.dithering
+    }
+
+    /// Frames that would draw more than the supply can give are scaled
+    /// down to fit
+    pub fn set_power_budget(&mut self, budget: PowerBudget) {
+        self.power_budget = Some(budget);
+    }
+
+    /// Estimated draw and limiting for the last frame written
+    pub fn power(&self) -> &PowerReport {
+        &self.power
+    }
+
+    pub fn led_count(&self) -> usize {
+        self.sections.led_count()
+    }
+
+    /// The color of every LED, as written to the sink before dithering
+    pub fn current_leds(&self) -> impl Iterator<Item = S::Color> + '_ {
+        let scale = self.power.scale;
+        let levels = self.blink_levels.unwrap_or([255; NUM_BLADES]);
+        led_colors(self.sections, &self.blade_data, &self.blade_white, levels)
+            .map(move |(color, white)| S::Color::render(color, white, &self.output).scale(scale))
+    }
+
+    /// Writes the blades to the sink if anything changed, including a
+    /// blink level, or on every call while dithering
+    pub fn refresh(&mut self, updated: bool, clock: &dyn Clock) -> Result<(), S::Error> {
+        let now_ms = (clock.now() - self.epoch).to_millis();
+        let levels = self.blink.map(|blink| blink.map_or(255, |blink| blink.level(now_ms)));
+
+        let updated = updated || self.dithering || self.blink_levels != Some(levels);
+        if !updated {
+            return Ok(());
+        }
+        self.blink_levels = Some(levels);
+
+        let output = self.output;
+        let colors = led_colors(self.sections, &self.blade_data, &self.blade_white, levels);
+
+        if let Some(budget) = self.power_budget {
+            let leds = colors.clone().map(|(color, white)| S::Color::render(color, white, &output));
+            let draw_ma = budget.estimate(leds);
+            let scale = budget.limit_scale(draw_ma, self.sections.led_count());
+            if scale < 255 {
+                self.power.limited_frames = self.power.limited_frames.wrapping_add(1);
+            }
+            self.power.draw_ma = draw_ma;
+            self.power.scale = scale;
+        }
+
+        let scale = self.power.scale;
+        let dithering = self.dithering;
+        let leds = colors.zip(self.dither_error.iter_mut()).map(|((color, white), error)| {
+            let led = if dithering {
+                S::Color::render_dithered(color, white, &output, error)
+            } else {
+                S::Color::render(color, white, &output)
+            };
+            led.scale(scale)
+        });
+        self.sink.write(leds)
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+}
+
+/// Expands blade colors and white levels onto the LEDs, dimmed by each
+/// blade's blink level
+fn led_colors<'a>(
+    sections: &'a SectionMap,
+    blade_data: &'a [RGB8; NUM_BLADES],
+    blade_white: &'a [u8; NUM_BLADES],
+    blink_levels: [u8; NUM_BLADES],
+) -> impl Iterator<Item = (RGB8, u8)> + Clone + 'a {
+    (0..sections.led_count()).map(move |led| match sections.blade_for_led(led) {
+        Some(blade) => {
+            let level = blink_levels[blade];
+            (nscale8(blade_data[blade], level), scale8(blade_white[blade], level))
+        }
+        None => (RGB8::default(), 0),
+    })
+}
+
+impl<S> BladeSink for BladeLights<S>
+where
+    S: SmartLedsWrite,
+    S::Color: Pixel,
+{
+    fn set_blade(&mut self, blade: u8, color: RGB8, blink: bool) -> Result<(), &'static str> {
+        BladeLights::set_blade(self, blade, color, blink)
+    }
+
+    fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        BladeLights::set_white(self, blade, white)
+    }
+
+    fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        BladeLights::set_blink(self, blade, blink)
+    }
+}
@@ -0,0 +1,65 @@
+//! Fixed-point 8-bit color math
+//!
+//! Fractions are expressed as `u8` where 255 is (almost exactly) 1.0,
+//! so the same integer operations give bit-exact results on the board
+//! and on the host, without touching the FPU.
+
+use smart_leds::RGB8;
+
+/// Scales `value` by `scale`/256, with 255 leaving the value unchanged
+pub fn scale8(value: u8, scale: u8) -> u8 {
+    ((value as u16 * (scale as u16 + 1)) >> 8) as u8
+}
+
+/// Like `scale8`, but never scales a non zero value down to zero
+/// unless `scale` is zero
+pub fn scale8_video(value: u8, scale: u8) -> u8 {
+    let scaled = scale8(value, scale);
+    if scaled == 0 && value != 0 && scale != 0 { 1 } else { scaled }
+}
+
+/// Saturating add
+pub fn qadd8(a: u8, b: u8) -> u8 {
+    a.saturating_add(b)
+}
+
+/// Saturating subtract
+pub fn qsub8(a: u8, b: u8) -> u8 {
+    a.saturating_sub(b)
+}
+
+/// Linear interpolation, `frac` 0 gives `a` and 255 gives `b`
+pub fn lerp8(a: u8, b: u8, frac: u8) -> u8 {
+    if b >= a {
+        a + scale8(b - a, frac)
+    } else {
+        a - scale8(a - b, frac)
+    }
+}
+
+/// Converts a fraction `numerator`/`denominator` to a `u8` scale
+pub fn fraction8(numerator: u32, denominator: u32) -> u8 {
+    if denominator == 0 {
+        return 0;
+    }
+    ((numerator.min(denominator) * 255) / denominator) as u8
+}
+
+/// Scales every channel of a color
+pub fn nscale8(color: RGB8, scale: u8) -> RGB8 {
+    RGB8::new(scale8(color.r, scale), scale8(color.g, scale), scale8(color.b, scale))
+}
+
+/// Blends two colors, `amount` 0 gives `from` and 255 gives `to`
+pub fn blend(from: RGB8, to: RGB8, amount: u8) -> RGB8 {
+    RGB8::new(
+        lerp8(from.r, to.r, amount),
+        lerp8(from.g, to.g, amount),
+        lerp8(from.b, to.b, amount),
+    )
+}
+
+/// Saturating per channel add of two colors
+pub fn add_color(a: RGB8, b: RGB8) -> RGB8 {
+    RGB8::new(qadd8(a.r, b.r), qadd8(a.g, b.g), qadd8(a.b, b.b))
+}
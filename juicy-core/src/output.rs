@@ -0,0 +1,417 @@
+//! Output stage applied to every LED just before it is written
+//!
+//! Corrects for how LEDs and eyes respond (gamma), for strips whose white
+//! has a tint (white balance), and for strips that expect their color
+//! channels in a different order. RGBW strips also get their white
+//! channel here, pulled out of the RGB color and topped up with any white
+//! the effect asked for directly.
+
+use smart_leds::{White, RGB8, RGBW};
+
+use crate::math8::{nscale8, qadd8, qsub8, scale8};
+
+/// SK6812 style RGBW color
+pub type RGBW8 = RGBW<u8>;
+
+/// Order a strip expects the color channels in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorOrder {
+    RGB,
+    RBG,
+    GRB,
+    GBR,
+    BRG,
+    BGR,
+}
+
+impl ColorOrder {
+    /// Channels of `color` in the order they go on the wire
+    pub const fn arrange(&self, color: RGB8) -> [u8; 3] {
+        let (r, g, b) = (color.r, color.g, color.b);
+        match self {
+            ColorOrder::RGB => [r, g, b],
+            ColorOrder::RBG => [r, b, g],
+            ColorOrder::GRB => [g, r, b],
+            ColorOrder::GBR => [g, b, r],
+            ColorOrder::BRG => [b, r, g],
+            ColorOrder::BGR => [b, g, r],
+        }
+    }
+
+    /// Inverse of `arrange`
+    pub const fn unarrange(&self, wire: [u8; 3]) -> RGB8 {
+        let [a, b, c] = wire;
+        match self {
+            ColorOrder::RGB => RGB8 { r: a, g: b, b: c },
+            ColorOrder::RBG => RGB8 { r: a, g: c, b },
+            ColorOrder::GRB => RGB8 { r: b, g: a, b: c },
+            ColorOrder::GBR => RGB8 { r: c, g: a, b },
+            ColorOrder::BRG => RGB8 { r: b, g: c, b: a },
+            ColorOrder::BGR => RGB8 { r: c, g: b, b: a },
+        }
+    }
+}
+
+/// Gamma curves, lookup tables are built at compile time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gamma {
+    Linear,
+    Gamma18,
+    Gamma22,
+    Gamma25,
+    Gamma28,
+}
+
+impl Gamma {
+    pub fn table(&self) -> &'static [u8; 256] {
+        match self {
+            Gamma::Linear => &LINEAR,
+            Gamma::Gamma18 => &GAMMA_18,
+            Gamma::Gamma22 => &GAMMA_22,
+            Gamma::Gamma25 => &GAMMA_25,
+            Gamma::Gamma28 => &GAMMA_28,
+        }
+    }
+
+    /// 16-bit version, for drivers that can show more than 8 bits
+    pub fn table16(&self) -> &'static [u16; 256] {
+        match self {
+            Gamma::Linear => &LINEAR_16,
+            Gamma::Gamma18 => &GAMMA_18_16,
+            Gamma::Gamma22 => &GAMMA_22_16,
+            Gamma::Gamma25 => &GAMMA_25_16,
+            Gamma::Gamma28 => &GAMMA_28_16,
+        }
+    }
+}
+
+static LINEAR: [u8; 256] = gamma_table(1.0);
+static GAMMA_18: [u8; 256] = gamma_table(1.8);
+static GAMMA_22: [u8; 256] = gamma_table(2.2);
+static GAMMA_25: [u8; 256] = gamma_table(2.5);
+static GAMMA_28: [u8; 256] = gamma_table(2.8);
+
+static LINEAR_16: [u16; 256] = gamma_table16(1.0);
+static GAMMA_18_16: [u16; 256] = gamma_table16(1.8);
+static GAMMA_22_16: [u16; 256] = gamma_table16(2.2);
+static GAMMA_25_16: [u16; 256] = gamma_table16(2.5);
+static GAMMA_28_16: [u16; 256] = gamma_table16(2.8);
+
+/// `255 * (i / 255) ^ gamma`, rounded, for every `i`
+const fn gamma_table(gamma: f64) -> [u8; 256] {
+    let mut table = [0u8; 256];
+
+    let mut i = 1;
+    while i < 256 {
+        let value = exp(gamma * ln(i as f64 / 255.0)) * 255.0 + 0.5;
+        table[i] = if value >= 255.0 { 255 } else { value as u8 };
+        i += 1;
+    }
+
+    table
+}
+
+/// `65535 * (i / 255) ^ gamma`, rounded, for every `i`
+const fn gamma_table16(gamma: f64) -> [u16; 256] {
+    let mut table = [0u16; 256];
+
+    let mut i = 1;
+    while i < 256 {
+        let value = exp(gamma * ln(i as f64 / 255.0)) * 65535.0 + 0.5;
+        table[i] = if value >= 65535.0 { 65535 } else { value as u16 };
+        i += 1;
+    }
+
+    table
+}
+
+/// Natural log for `0 < x <= 1`, good to well under one LSB of a `u8`
+const fn ln(x: f64) -> f64 {
+    // Scale into [0.5, 1) so the series converges quickly
+    let mut m = x;
+    let mut exponent = 0.0;
+    while m < 0.5 {
+        m *= 2.0;
+        exponent -= 1.0;
+    }
+
+    // ln(m) = 2 * atanh((m - 1) / (m + 1))
+    let t = (m - 1.0) / (m + 1.0);
+    let t2 = t * t;
+    let mut term = t;
+    let mut sum = 0.0;
+    let mut n = 1.0;
+    while n < 40.0 {
+        sum += term / n;
+        term *= t2;
+        n += 2.0;
+    }
+
+    2.0 * sum + exponent * core::f64::consts::LN_2
+}
+
+/// e^y for `y <= 0`
+const fn exp(y: f64) -> f64 {
+    // Halve until small, then square back up
+    let mut y = y;
+    let mut halvings = 0;
+    while y < -0.5 {
+        y /= 2.0;
+        halvings += 1;
+    }
+
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut n = 1.0;
+    while n < 20.0 {
+        term *= y / n;
+        sum += term;
+        n += 1.0;
+    }
+
+    while halvings > 0 {
+        sum *= sum;
+        halvings -= 1;
+    }
+
+    sum
+}
+
+/// How an RGBW strip's white LED is driven from an RGB color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WhiteMode {
+    /// Only white set directly by an effect
+    Off,
+    /// The common part of R, G and B moves to the white LED
+    MinOfRgb,
+    /// As `MinOfRgb`, for a white LED that looks like this RGB color at
+    /// full power (eg warm white around `(255, 200, 140)`)
+    Calibrated(RGB8),
+}
+
+impl WhiteMode {
+    /// Splits `color` into what the RGB LEDs still show and the white level
+    pub fn extract(&self, color: RGB8) -> (RGB8, u8) {
+        let tint = match self {
+            WhiteMode::Off => return (color, 0),
+            WhiteMode::MinOfRgb => RGB8::new(255, 255, 255),
+            WhiteMode::Calibrated(tint) => *tint,
+        };
+
+        // Most white the color can give up without any channel going negative
+        let mut white = 255u8;
+        for (channel, tint) in [(color.r, tint.r), (color.g, tint.g), (color.b, tint.b)] {
+            if tint > 0 {
+                white = white.min(((channel as u16 * 255) / tint as u16).min(255) as u8);
+            }
+        }
+
+        let rgb = RGB8::new(
+            qsub8(color.r, scale8(tint.r, white)),
+            qsub8(color.g, scale8(tint.g, white)),
+            qsub8(color.b, scale8(tint.b, white)),
+        );
+
+        (rgb, white)
+    }
+}
+
+/// Gamma, white balance and channel order for one strip
+#[derive(Clone, Copy)]
+pub struct OutputStage {
+    gamma: Gamma,
+    white_balance: RGB8,
+    strip_order: ColorOrder,
+    driver_order: ColorOrder,
+    white_mode: WhiteMode,
+}
+
+impl OutputStage {
+    /// Passes colors through untouched
+    pub const fn new() -> Self {
+        Self {
+            gamma: Gamma::Linear,
+            white_balance: RGB8 { r: 255, g: 255, b: 255 },
+            strip_order: ColorOrder::GRB,
+            driver_order: ColorOrder::GRB,
+            white_mode: WhiteMode::MinOfRgb,
+        }
+    }
+
+    pub const fn with_gamma(mut self, gamma: Gamma) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Scales each channel, 255 leaves it at full strength
+    pub const fn with_white_balance(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.white_balance = RGB8 { r, g, b };
+        self
+    }
+
+    /// `strip` is the order the LEDs expect, `driver` the order the
+    /// driver sends an `RGB8` in (GRB for WS2812 drivers)
+    pub const fn with_color_order(mut self, strip: ColorOrder, driver: ColorOrder) -> Self {
+        self.strip_order = strip;
+        self.driver_order = driver;
+        self
+    }
+
+    /// Only used by RGBW strips
+    pub const fn with_white_mode(mut self, white_mode: WhiteMode) -> Self {
+        self.white_mode = white_mode;
+        self
+    }
+
+    pub fn gamma(&self) -> Gamma {
+        self.gamma
+    }
+
+    pub fn white_mode(&self) -> WhiteMode {
+        self.white_mode
+    }
+
+    pub fn apply(&self, color: RGB8) -> RGB8 {
+        self.reorder(self.correct(color))
+    }
+
+    /// White balance then gamma
+    pub fn correct(&self, color: RGB8) -> RGB8 {
+        let table = self.gamma.table();
+        RGB8::new(
+            table[scale8(color.r, self.white_balance.r) as usize],
+            table[scale8(color.g, self.white_balance.g) as usize],
+            table[scale8(color.b, self.white_balance.b) as usize],
+        )
+    }
+
+    /// White balance then 16-bit gamma, as `[r, g, b]`
+    pub fn correct16(&self, color: RGB8) -> [u16; 3] {
+        let table = self.gamma.table16();
+        [
+            table[scale8(color.r, self.white_balance.r) as usize],
+            table[scale8(color.g, self.white_balance.g) as usize],
+            table[scale8(color.b, self.white_balance.b) as usize],
+        ]
+    }
+
+    /// Gamma for a white channel
+    pub fn correct_white(&self, white: u8) -> u8 {
+        self.gamma.table()[white as usize]
+    }
+
+    /// `correct` at 16 bits, rounded down to 8 with the remainder carried
+    /// over in `error`, so over several refreshes the LED averages out to
+    /// the exact level
+    pub fn correct_dithered(&self, color: RGB8, error: &mut DitherError) -> RGB8 {
+        let [r, g, b] = self.correct16(color);
+        RGB8::new(
+            dither(r, &mut error[0]),
+            dither(g, &mut error[1]),
+            dither(b, &mut error[2]),
+        )
+    }
+
+    /// `correct_white` with dithering
+    pub fn correct_white_dithered(&self, white: u8, error: &mut u8) -> u8 {
+        dither(self.gamma.table16()[white as usize], error)
+    }
+
+    /// Swaps channels so the driver puts them on the wire in strip order
+    pub fn reorder(&self, color: RGB8) -> RGB8 {
+        if self.strip_order == self.driver_order {
+            return color;
+        }
+
+        self.driver_order.unarrange(self.strip_order.arrange(color))
+    }
+}
+
+impl Default for OutputStage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fraction of each channel (R, G, B, W) still owed to an LED, in 1/256ths
+pub type DitherError = [u8; 4];
+
+fn dither(value: u16, error: &mut u8) -> u8 {
+    let sum = (value & 0xff) + *error as u16;
+    *error = sum as u8;
+    ((value >> 8) + (sum >> 8)).min(255) as u8
+}
+
+/// Colors `BladeLights` can write, built from a blade's RGB color and the
+/// white an effect set directly
+pub trait Pixel: Copy + Default {
+    fn render(color: RGB8, white: u8, output: &OutputStage) -> Self;
+
+    /// `render` with temporal dithering, `error` is kept per LED between
+    /// refreshes. Types with their own way of showing dim levels can leave
+    /// this as plain `render`.
+    fn render_dithered(color: RGB8, white: u8, output: &OutputStage, error: &mut DitherError) -> Self {
+        let _ = error;
+        Self::render(color, white, output)
+    }
+
+    /// Sum of the channel drive levels, 255 for each channel fully on
+    fn level(&self) -> u32;
+
+    /// Dims every channel, 255 leaves the LED unchanged
+    fn scale(self, scale: u8) -> Self;
+}
+
+/// Direct white is mixed into all three channels
+impl Pixel for RGB8 {
+    fn render(color: RGB8, white: u8, output: &OutputStage) -> Self {
+        let color = RGB8::new(qadd8(color.r, white), qadd8(color.g, white), qadd8(color.b, white));
+        output.apply(color)
+    }
+
+    fn render_dithered(color: RGB8, white: u8, output: &OutputStage, error: &mut DitherError) -> Self {
+        let color = RGB8::new(qadd8(color.r, white), qadd8(color.g, white), qadd8(color.b, white));
+        output.reorder(output.correct_dithered(color, error))
+    }
+
+    fn level(&self) -> u32 {
+        self.r as u32 + self.g as u32 + self.b as u32
+    }
+
+    fn scale(self, scale: u8) -> Self {
+        nscale8(self, scale)
+    }
+}
+
+/// White is extracted after gamma, so the strip gives off the same light
+/// as the RGB color would have
+impl Pixel for RGBW8 {
+    fn render(color: RGB8, white: u8, output: &OutputStage) -> Self {
+        let (rgb, extracted) = output.white_mode().extract(output.correct(color));
+        let rgb = output.reorder(rgb);
+        let white = qadd8(extracted, output.correct_white(white));
+
+        RGBW8 { r: rgb.r, g: rgb.g, b: rgb.b, a: White(white) }
+    }
+
+    fn render_dithered(color: RGB8, white: u8, output: &OutputStage, error: &mut DitherError) -> Self {
+        let (rgb, extracted) = output.white_mode().extract(output.correct_dithered(color, error));
+        let rgb = output.reorder(rgb);
+        let white = qadd8(extracted, output.correct_white_dithered(white, &mut error[3]));
+
+        RGBW8 { r: rgb.r, g: rgb.g, b: rgb.b, a: White(white) }
+    }
+
+    fn level(&self) -> u32 {
+        self.r as u32 + self.g as u32 + self.b as u32 + self.a.0 as u32
+    }
+
+    fn scale(self, scale: u8) -> Self {
+        RGBW8 {
+            r: scale8(self.r, scale),
+            g: scale8(self.g, scale),
+            b: scale8(self.b, scale),
+            a: White(scale8(self.a.0, scale)),
+        }
+    }
+}
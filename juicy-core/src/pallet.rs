@@ -1,6 +1,8 @@
 
 use smart_leds::RGB8;
 
+use crate::math8::scale8;
+
 #[allow(dead_code)]
 pub enum Colors {
     Black,
@@ -59,27 +61,17 @@ pub fn adjust_temperature(temperature: u8, adjust: i8) -> u8 {
 /// Uses a global brightness value scaled by the level
 pub fn get_color_bright(temperature: u8, bright: u8) -> RGB8 {
     let tint = (temperature & 0xf0) >> 4;
-    let level = (temperature & 0x0f) as f32 / 15.0;
+    // Levels 0-15 map onto 0-255 exactly
+    let full = scale8(bright, (temperature & 0x0f) * 17);
+    let half = scale8(full, 128);
 
     match tint {
-        1 => RGB8::new((bright as f32 * level) as u8, 0, 0),
-        2 => RGB8::new(0, (bright as f32 * level) as u8, 0),
-        3 => RGB8::new(0, 0, (bright as f32 * level) as u8),
-        4 => RGB8::new(
-            (bright as f32 * level * 0.5) as u8,
-            0,
-            (bright as f32 * level * 0.5) as u8,
-        ),
-        5 => RGB8::new(
-            (bright as f32 * level * 0.5) as u8,
-            (bright as f32 * level * 0.5) as u8,
-            0,
-        ),
-        6 => RGB8::new(
-            0,
-            (bright as f32 * level * 0.5) as u8,
-            (bright as f32 * level * 0.5) as u8,
-        ),
+        1 => RGB8::new(full, 0, 0),
+        2 => RGB8::new(0, full, 0),
+        3 => RGB8::new(0, 0, full),
+        4 => RGB8::new(half, 0, half),
+        5 => RGB8::new(half, half, 0),
+        6 => RGB8::new(0, half, half),
         _ => RGB8::new(0, 0, 0),
     }
 }
@@ -0,0 +1,202 @@
+use heapless::Vec;
+
+use crate::rng::Rng;
+use crate::effects::{find_effect, Effect, EffectParams, ShellSparkFireEffect, EFFECT_REGISTRY};
+
+pub const MAX_PLAYLIST_ENTRIES: usize = 16;
+
+/// One step of the effect rotation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaylistEntry {
+    /// Index into `EFFECT_REGISTRY`
+    pub effect_id: usize,
+    pub brightness: u8,
+    pub delay_ms: u32,
+    pub duration_sec: u32,
+    /// Effect specific tuning value, see the effect for its meaning
+    pub param: Option<u32>,
+    /// Relative chance of being picked in `PlaylistOrder::WeightedRandom`
+    pub weight: u8,
+}
+
+impl PlaylistEntry {
+    pub fn new(effect_id: usize, brightness: u8, delay_ms: u32, duration_sec: u32) -> Self {
+        Self {
+            effect_id,
+            brightness,
+            delay_ms,
+            duration_sec,
+            param: None,
+            weight: 1,
+        }
+    }
+
+    pub fn params(&self) -> EffectParams {
+        EffectParams {
+            brightness: self.brightness,
+            delay_ms: self.delay_ms,
+            param: self.param,
+        }
+    }
+}
+
+/// Order in which playlist entries are played
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaylistOrder {
+    Sequential,
+    /// Every entry once per pass, in a new random order each pass
+    Shuffle,
+    /// Independent picks, biased by each entry's weight
+    WeightedRandom,
+}
+
+pub struct Playlist {
+    entries: Vec<PlaylistEntry, MAX_PLAYLIST_ENTRIES>,
+    order: PlaylistOrder,
+    position: usize,
+    last: Option<usize>,
+    shuffle_bag: Vec<u8, MAX_PLAYLIST_ENTRIES>,
+}
+
+impl Playlist {
+    pub fn new(order: PlaylistOrder) -> Self {
+        Self {
+            entries: Vec::new(),
+            order,
+            position: 0,
+            last: None,
+            shuffle_bag: Vec::new(),
+        }
+    }
+
+    /// The rotation the shell has always used: every registered effect
+    /// with its default parameters for 60 seconds, starting with spark fire
+    pub fn default_rotation() -> Self {
+        let mut playlist = Self::new(PlaylistOrder::Sequential);
+        let count = EFFECT_REGISTRY.len();
+        let start = find_effect(ShellSparkFireEffect::NAME).unwrap_or(0);
+
+        for i in 0..count {
+            let effect_id = (start + i) % count;
+            let params = (EFFECT_REGISTRY[effect_id].default_params)();
+            let mut entry = PlaylistEntry::new(effect_id, params.brightness, params.delay_ms, 60);
+            entry.param = params.param;
+            let _ = playlist.push(entry);
+        }
+
+        playlist
+    }
+
+    pub fn push(&mut self, entry: PlaylistEntry) -> Result<(), &'static str> {
+        if entry.effect_id >= EFFECT_REGISTRY.len() {
+            return Err("effect id out of range")
+        }
+        if entry.duration_sec == 0 {
+            return Err("duration must be at least a second")
+        }
+
+        self.entries.push(entry).map_err(|_| "playlist full")?;
+        self.shuffle_bag.clear();
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.rewind();
+    }
+
+    pub fn set_order(&mut self, order: PlaylistOrder) {
+        self.order = order;
+        self.shuffle_bag.clear();
+    }
+
+    pub fn order(&self) -> PlaylistOrder {
+        self.order
+    }
+
+    /// Starts again from the first entry, or a fresh shuffle
+    pub fn rewind(&mut self) {
+        self.position = 0;
+        self.last = None;
+        self.shuffle_bag.clear();
+    }
+
+    pub fn entries(&self) -> &[PlaylistEntry] {
+        &self.entries
+    }
+
+    /// Picks the next entry to play, never the same entry twice in a row
+    /// unless it is the only one
+    pub fn next(&mut self, rng: &mut Rng) -> Option<PlaylistEntry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let index = match self.order {
+            PlaylistOrder::Sequential => {
+                let index = self.position % self.entries.len();
+                self.position = index + 1;
+                index
+            }
+            PlaylistOrder::Shuffle => self.next_shuffled(rng),
+            PlaylistOrder::WeightedRandom => self.next_weighted(rng),
+        };
+
+        self.last = Some(index);
+        Some(self.entries[index])
+    }
+
+    fn next_shuffled(&mut self, rng: &mut Rng) -> usize {
+        if self.shuffle_bag.is_empty() {
+            // Refill the bag in reverse so entries are popped off the end
+            for i in (0..self.entries.len()).rev() {
+                let _ = self.shuffle_bag.push(i as u8);
+            }
+
+            // Fisher-Yates shuffle
+            for i in (1..self.shuffle_bag.len()).rev() {
+                let j = rng.range(i as u32 + 1) as usize;
+                self.shuffle_bag.swap(i, j);
+            }
+
+            // Don't start a new pass with the entry that ended the last one
+            let top = self.shuffle_bag.len() - 1;
+            if top > 0 && Some(self.shuffle_bag[top] as usize) == self.last {
+                self.shuffle_bag.swap(top, 0);
+            }
+        }
+
+        self.shuffle_bag.pop().unwrap_or(0) as usize
+    }
+
+    fn next_weighted(&mut self, rng: &mut Rng) -> usize {
+        let excluded = if self.entries.len() > 1 { self.last } else { None };
+
+        let total: u32 = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != excluded)
+            .map(|(_, entry)| entry.weight as u32)
+            .sum();
+
+        // All remaining weights are zero, fall back to the next entry in order
+        if total == 0 {
+            return excluded.map_or(0, |last| (last + 1) % self.entries.len());
+        }
+
+        let mut pick = rng.range(total);
+        for (i, entry) in self.entries.iter().enumerate() {
+            if Some(i) == excluded {
+                continue;
+            }
+            if pick < entry.weight as u32 {
+                return i;
+            }
+            pick -= entry.weight as u32;
+        }
+
+        0
+    }
+}
@@ -0,0 +1,86 @@
+//! Supply current estimate and limiting
+//!
+//! Each LED channel draws roughly in proportion to its PWM level, so the
+//! current for a frame can be estimated from the values about to be
+//! written. When that is more than the supply can give the whole frame is
+//! scaled down, keeping the colors and their balance.
+
+use crate::output::Pixel;
+
+/// What the strip draws and what the supply can give
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerBudget {
+    /// mA one channel draws at full level, about 20 for WS2812
+    pub ma_per_channel: u32,
+    /// mA each LED draws when dark
+    pub idle_ma_per_led: u32,
+    /// Most the supply can give the LEDs
+    pub supply_ma: u32,
+}
+
+impl PowerBudget {
+    pub const fn new(ma_per_channel: u32, idle_ma_per_led: u32, supply_ma: u32) -> Self {
+        Self {
+            ma_per_channel,
+            idle_ma_per_led,
+            supply_ma,
+        }
+    }
+
+    /// Estimated mA for a frame
+    pub fn estimate<P, I>(&self, leds: I) -> u32
+    where
+        P: Pixel,
+        I: IntoIterator<Item = P>,
+    {
+        let mut count = 0;
+        let mut level = 0;
+        for led in leds {
+            count += 1;
+            level += led.level();
+        }
+
+        self.idle_ma(count) + self.level_ma(level)
+    }
+
+    /// Scale for every channel that brings `draw_ma` within the supply,
+    /// 255 when it already fits
+    pub fn limit_scale(&self, draw_ma: u32, led_count: usize) -> u8 {
+        if draw_ma <= self.supply_ma {
+            return 255;
+        }
+
+        // The idle current can't be scaled away
+        let idle = self.idle_ma(led_count);
+        let available = self.supply_ma.saturating_sub(idle) as u64;
+        let scalable = draw_ma.saturating_sub(idle).max(1) as u64;
+
+        // Round down so the limited frame stays within budget
+        ((available * 256) / scalable).saturating_sub(1).min(255) as u8
+    }
+
+    fn idle_ma(&self, led_count: usize) -> u32 {
+        led_count as u32 * self.idle_ma_per_led
+    }
+
+    fn level_ma(&self, level: u32) -> u32 {
+        ((level as u64 * self.ma_per_channel as u64) / 255) as u32
+    }
+}
+
+/// Result of the last refresh
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PowerReport {
+    /// Estimated mA the frame would have drawn
+    pub draw_ma: u32,
+    /// Scale applied to stay within the supply, 255 when not limited
+    pub scale: u8,
+    /// Frames that had to be scaled down since start up
+    pub limited_frames: u32,
+}
+
+impl PowerReport {
+    pub fn is_limited(&self) -> bool {
+        self.scale < 255
+    }
+}
@@ -0,0 +1,57 @@
+/// Seed used when nothing better is available, and by the tests
+pub const DEFAULT_SEED: u32 = 0x12345678;
+
+/// Small, fast xorshift32 generator shared by all effects
+///
+/// Given the same seed it always produces the same sequence, which keeps
+/// effect frames reproducible on the host.
+#[derive(Clone, Copy)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // xorshift gets stuck at zero, so remap that seed
+        let state = if seed == 0 { DEFAULT_SEED } else { seed };
+        Self { state }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform value in 0..n, returns 0 when n is 0
+    pub fn range(&mut self, n: u32) -> u32 {
+        ((self.next_u32() as u64 * n as u64) >> 32) as u32
+    }
+
+    /// Uniform value in low..high
+    pub fn range_between(&mut self, low: u32, high: u32) -> u32 {
+        low + self.range(high.saturating_sub(low))
+    }
+
+    /// True with a chance of 1 in `odds`
+    pub fn one_in(&mut self, odds: u32) -> bool {
+        odds <= 1 || self.range(odds) == 0
+    }
+
+    /// Picks a random element, `None` for an empty slice
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.range(items.len() as u32) as usize)
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
@@ -0,0 +1,108 @@
+//! Mapping of blades onto physical LEDs
+//!
+//! A blade (section) can be a run of consecutive LEDs or an arbitrary list
+//! of them, so larger shells can be built without changing any effect.
+//! Maps are built by `const fn`, so a bad table is a compile error
+//! rather than a runtime surprise.
+
+use crate::frame::NUM_BLADES;
+
+/// Largest strip a section map can describe
+pub const MAX_LEDS: usize = 512;
+
+/// Marks an LED that no blade drives
+const UNMAPPED: u8 = u8::MAX;
+
+/// The LEDs making up one blade
+pub enum Section {
+    /// `len` consecutive LEDs starting at `start`
+    Range { start: u16, len: u16 },
+    /// Any set of LEDs
+    List(&'static [u16]),
+}
+
+pub struct SectionMap {
+    led_count: usize,
+    led_to_blade: [u8; MAX_LEDS],
+}
+
+impl SectionMap {
+    /// Builds a map from one section per blade, over a strip of
+    /// `led_count` LEDs
+    ///
+    /// Panics (at compile time when used in a `const`) if the table
+    /// doesn't have a section for every blade, references an LED outside
+    /// the strip, or puts an LED in more than one section.
+    pub const fn new(sections: &[Section], led_count: usize) -> Self {
+        assert!(led_count <= MAX_LEDS, "section map has more LEDs than MAX_LEDS");
+        assert!(sections.len() == NUM_BLADES, "section map needs one section per blade");
+
+        let mut led_to_blade = [UNMAPPED; MAX_LEDS];
+
+        let mut blade = 0;
+        while blade < sections.len() {
+            match &sections[blade] {
+                Section::Range { start, len } => {
+                    let mut led = *start as usize;
+                    while led < *start as usize + *len as usize {
+                        Self::assign(&mut led_to_blade, led, blade, led_count);
+                        led += 1;
+                    }
+                }
+                Section::List(leds) => {
+                    let mut i = 0;
+                    while i < leds.len() {
+                        Self::assign(&mut led_to_blade, leds[i] as usize, blade, led_count);
+                        i += 1;
+                    }
+                }
+            }
+            blade += 1;
+        }
+
+        Self { led_count, led_to_blade }
+    }
+
+    const fn assign(led_to_blade: &mut [u8; MAX_LEDS], led: usize, blade: usize, led_count: usize) {
+        assert!(led < led_count, "section references an LED past the end of the strip");
+        assert!(led_to_blade[led] == UNMAPPED, "LED is in more than one section");
+        led_to_blade[led] = blade as u8;
+    }
+
+    /// One LED per blade, in blade order
+    pub const fn one_per_blade() -> Self {
+        let mut led_to_blade = [UNMAPPED; MAX_LEDS];
+
+        let mut blade = 0;
+        while blade < NUM_BLADES {
+            led_to_blade[blade] = blade as u8;
+            blade += 1;
+        }
+
+        Self { led_count: NUM_BLADES, led_to_blade }
+    }
+
+    pub const fn led_count(&self) -> usize {
+        self.led_count
+    }
+
+    /// The blade driving an LED, `None` for LEDs outside every section
+    pub fn blade_for_led(&self, led: usize) -> Option<usize> {
+        if led >= self.led_count {
+            return None;
+        }
+
+        match self.led_to_blade[led] {
+            UNMAPPED => None,
+            blade => Some(blade as usize),
+        }
+    }
+
+    /// The LEDs belonging to a blade
+    pub fn leds_for_blade(&self, blade: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.led_count).filter(move |led| self.led_to_blade[*led] as usize == blade)
+    }
+}
+
+/// One LED per blade, the layout of the original shell
+pub static ONE_PER_BLADE: SectionMap = SectionMap::one_per_blade();
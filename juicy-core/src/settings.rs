@@ -0,0 +1,325 @@
+//! Settings kept across power cycles
+//!
+//! Only what was chosen by hand is kept: the playlist and its order, the
+//! master brightness and an effect picked with its params and duration.
+//! Where the playlist has got to is not, so the rotation alone never
+//! writes to flash.
+//!
+//! The settings are saved as a fixed size record in a reserved flash
+//! area. Each save goes into the next erased slot, so the area is only
+//! erased once every slot has been used, and at boot the valid record
+//! with the highest sequence number wins. Records carry a version and a
+//! CRC so old formats and torn writes are ignored rather than restored.
+//!
+//! ```text
+//! 0   magic "JSET"
+//! 4   version
+//! 5   playlist entries
+//! 6   playlist order
+//! 7   brightness
+//! 8   sequence        u32
+//! 12  selected effect 16 bytes, effect id 0xff for none
+//! 28  playlist        16 bytes per entry
+//! 508 CRC-32          over bytes 0..508
+//! ```
+
+use fugit::Instant;
+use heapless::Vec;
+
+use crate::clock::Clock;
+use crate::crc::crc32;
+use crate::effects::{EffectManager, EFFECT_REGISTRY};
+use crate::playlist::{PlaylistEntry, PlaylistOrder, MAX_PLAYLIST_ENTRIES};
+
+/// Bytes per record slot, the flash area is a whole number of slots
+pub const RECORD_SIZE: usize = 512;
+/// Bumped whenever the record layout changes
+pub const VERSION: u8 = 2;
+
+const MAGIC: [u8; 4] = *b"JSET";
+const SELECTED_OFFSET: usize = 12;
+const PLAYLIST_OFFSET: usize = 28;
+const ENTRY_SIZE: usize = 16;
+const NO_EFFECT: u8 = 0xff;
+const CRC_OFFSET: usize = RECORD_SIZE - 4;
+
+/// The reserved flash area, erased bytes read as 0xFF
+pub trait SettingsFlash {
+    /// Bytes in the area, a multiple of `RECORD_SIZE`
+    fn size(&self) -> usize;
+
+    fn read(&self, offset: usize, buffer: &mut [u8]);
+
+    /// Programs erased bytes, `offset` is from the start of the area
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), &'static str>;
+
+    /// Erases the whole area
+    fn erase(&mut self) -> Result<(), &'static str>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Effect picked by hand, `None` leaves the playlist in charge
+    pub selected: Option<PlaylistEntry>,
+    pub brightness: u8,
+    pub order: PlaylistOrder,
+    pub playlist: Vec<PlaylistEntry, MAX_PLAYLIST_ENTRIES>,
+}
+
+impl Settings {
+    /// Takes the chosen settings from the running effects
+    pub fn capture(manager: &EffectManager) -> Self {
+        let playlist = manager.playlist();
+
+        Self {
+            selected: manager.selected().copied(),
+            brightness: manager.brightness(),
+            order: playlist.order(),
+            playlist: playlist.entries().iter().copied().collect(),
+        }
+    }
+
+    /// Restores the settings, cutting straight to the saved effect or
+    /// the start of the playlist
+    pub fn apply(&self, manager: &mut EffectManager, clock: &dyn Clock) -> Result<(), &'static str> {
+        let playlist = manager.playlist_mut();
+        playlist.clear();
+        playlist.set_order(self.order);
+        for entry in &self.playlist {
+            playlist.push(*entry)?;
+        }
+
+        match self.selected {
+            Some(entry) => manager.select_entry(entry, clock),
+            None => manager.restart_playlist(clock),
+        }
+        manager.skip_transition();
+        manager.set_brightness(self.brightness);
+
+        Ok(())
+    }
+
+    pub fn encode(&self, sequence: u32) -> [u8; RECORD_SIZE] {
+        let mut record = [0u8; RECORD_SIZE];
+
+        record[0..4].copy_from_slice(&MAGIC);
+        record[4] = VERSION;
+        record[5] = self.playlist.len() as u8;
+        record[6] = match self.order {
+            PlaylistOrder::Sequential => 0,
+            PlaylistOrder::Shuffle => 1,
+            PlaylistOrder::WeightedRandom => 2,
+        };
+        record[7] = self.brightness;
+        record[8..12].copy_from_slice(&sequence.to_le_bytes());
+
+        let selected = &mut record[SELECTED_OFFSET..SELECTED_OFFSET + ENTRY_SIZE];
+        match &self.selected {
+            Some(entry) => encode_entry(entry, selected),
+            None => selected[0] = NO_EFFECT,
+        }
+
+        for (entry, bytes) in self.playlist.iter().zip(record[PLAYLIST_OFFSET..].chunks_exact_mut(ENTRY_SIZE)) {
+            encode_entry(entry, bytes);
+        }
+
+        let crc = crc32(&record[..CRC_OFFSET]);
+        record[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+
+        record
+    }
+
+    /// Reads a record back, returning the settings and their sequence number
+    pub fn decode(record: &[u8; RECORD_SIZE]) -> Result<(Self, u32), &'static str> {
+        if record[0..4] != MAGIC {
+            return Err("no settings record");
+        }
+        if crc32(&record[..CRC_OFFSET]) != u32_at(record, CRC_OFFSET) {
+            return Err("settings record is corrupt");
+        }
+        if record[4] != VERSION {
+            return Err("unsupported settings version");
+        }
+
+        let count = record[5] as usize;
+        if count > MAX_PLAYLIST_ENTRIES {
+            return Err("settings record is out of range");
+        }
+
+        let order = match record[6] {
+            0 => PlaylistOrder::Sequential,
+            1 => PlaylistOrder::Shuffle,
+            2 => PlaylistOrder::WeightedRandom,
+            _ => return Err("settings record is out of range"),
+        };
+
+        let selected = &record[SELECTED_OFFSET..SELECTED_OFFSET + ENTRY_SIZE];
+        let selected = match selected[0] {
+            NO_EFFECT => None,
+            _ => Some(decode_entry(selected)?),
+        };
+
+        let mut playlist = Vec::new();
+        for bytes in record[PLAYLIST_OFFSET..].chunks_exact(ENTRY_SIZE).take(count) {
+            let _ = playlist.push(decode_entry(bytes)?);
+        }
+
+        let settings = Self {
+            selected,
+            brightness: record[7],
+            order,
+            playlist,
+        };
+
+        Ok((settings, u32_at(record, 8)))
+    }
+}
+
+fn encode_entry(entry: &PlaylistEntry, bytes: &mut [u8]) {
+    bytes[0] = entry.effect_id as u8;
+    bytes[1] = entry.brightness;
+    bytes[2] = entry.weight;
+    bytes[3] = entry.param.is_some() as u8;
+    bytes[4..8].copy_from_slice(&entry.delay_ms.to_le_bytes());
+    bytes[8..12].copy_from_slice(&entry.duration_sec.to_le_bytes());
+    bytes[12..16].copy_from_slice(&entry.param.unwrap_or(0).to_le_bytes());
+}
+
+fn decode_entry(bytes: &[u8]) -> Result<PlaylistEntry, &'static str> {
+    let mut entry = PlaylistEntry::new(bytes[0] as usize, bytes[1], u32_at(bytes, 4), u32_at(bytes, 8));
+    entry.weight = bytes[2];
+    entry.param = (bytes[3] != 0).then(|| u32_at(bytes, 12));
+
+    if entry.effect_id >= EFFECT_REGISTRY.len() || entry.duration_sec == 0 {
+        return Err("settings record is out of range");
+    }
+
+    Ok(entry)
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0u8; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+/// Settings records in a flash area, see the module docs
+pub struct SettingsStore<F> {
+    flash: F,
+    /// Slot the next save goes into, `None` once the area is full
+    next_slot: Option<usize>,
+    sequence: u32,
+}
+
+impl<F: SettingsFlash> SettingsStore<F> {
+    pub fn new(flash: F) -> Self {
+        Self {
+            flash,
+            next_slot: Some(0),
+            sequence: 0,
+        }
+    }
+
+    fn slots(&self) -> usize {
+        self.flash.size() / RECORD_SIZE
+    }
+
+    /// Finds the newest valid record and where the next one goes
+    pub fn load(&mut self) -> Option<Settings> {
+        let mut newest: Option<(Settings, u32)> = None;
+        let mut record = [0u8; RECORD_SIZE];
+        self.next_slot = Some(0);
+
+        for slot in 0..self.slots() {
+            self.flash.read(slot * RECORD_SIZE, &mut record);
+            if record.iter().all(|&byte| byte == 0xff) {
+                continue;
+            }
+
+            // Torn or unreadable records still use up their slot
+            self.next_slot = (slot + 1 < self.slots()).then_some(slot + 1);
+
+            if let Ok((settings, sequence)) = Settings::decode(&record) {
+                if newest.as_ref().is_none_or(|(_, newest)| sequence > *newest) {
+                    newest = Some((settings, sequence));
+                }
+            }
+        }
+
+        let (settings, sequence) = newest?;
+        self.sequence = sequence;
+        Some(settings)
+    }
+
+    /// Writes a new record, erasing the area first when it is full
+    ///
+    /// Erasing stalls the CPU while the flash is busy, and the settings
+    /// are lost if the power fails between the erase and the write.
+    pub fn save(&mut self, settings: &Settings) -> Result<(), &'static str> {
+        let slot = match self.next_slot {
+            Some(slot) => slot,
+            None => {
+                self.flash.erase()?;
+                0
+            }
+        };
+
+        self.sequence = self.sequence.wrapping_add(1);
+        let record = settings.encode(self.sequence);
+
+        // The slot is used up even if the write fails part way
+        self.next_slot = (slot + 1 < self.slots()).then_some(slot + 1);
+        self.flash.write(slot * RECORD_SIZE, &record)
+    }
+
+    pub fn flash(&self) -> &F {
+        &self.flash
+    }
+}
+
+/// Decides when changed settings are worth saving
+///
+/// Settings are saved once they have stayed the same for `delay_ms`, so
+/// a brightness slider doesn't write a record per step.
+pub struct SettingsWatch {
+    delay_ms: u32,
+    saved: Option<Settings>,
+    changed_at: Option<Instant<u32, 1, 1000>>,
+    pending: Option<Settings>,
+}
+
+impl SettingsWatch {
+    /// `saved` is what the flash already holds, if anything
+    pub fn new(delay_ms: u32, saved: Option<Settings>) -> Self {
+        Self {
+            delay_ms,
+            saved,
+            changed_at: None,
+            pending: None,
+        }
+    }
+
+    /// Returns the settings to save, if they changed and have settled
+    pub fn poll(&mut self, current: Settings, now: Instant<u32, 1, 1000>) -> Option<Settings> {
+        if self.saved.as_ref() == Some(&current) {
+            self.pending = None;
+            self.changed_at = None;
+            return None;
+        }
+
+        if self.pending.as_ref() != Some(&current) {
+            self.pending = Some(current);
+            self.changed_at = Some(now);
+            return None;
+        }
+
+        let changed_at = self.changed_at?;
+        if (now - changed_at).to_millis() < self.delay_ms {
+            return None;
+        }
+
+        self.saved = self.pending.take();
+        self.changed_at = None;
+        self.saved.clone()
+    }
+}
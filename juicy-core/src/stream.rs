@@ -0,0 +1,303 @@
+//! Live frames streamed from a PC, in Adalight or TPM2 format
+//!
+//! Desktop lighting software sends whole frames over the serial port that
+//! also carries the console. The decoder picks the frames out of the byte
+//! stream and passes everything else through as console text.
+//!
+//! Adalight: `Ada`, LED count - 1 (big endian), checksum of the two count
+//! bytes xor 0x55, then RGB for each LED.
+//!
+//! TPM2: 0xC9, frame type (0xDA for data), payload size (big endian), the
+//! payload, then 0x36.
+//!
+//! Streamed LED `i` drives blade `i`, extra LEDs are ignored and blades
+//! past the end of a short frame are dark.
+//!
+//! A frame that stalls for `BYTE_TIMEOUT_MS` is dropped, so a lost byte
+//! can't leave the decoder eating console text as LED data.
+
+use fugit::Instant;
+use smart_leds::RGB8;
+
+use crate::frame::NUM_BLADES;
+
+pub const TPM2_START: u8 = 0xc9;
+pub const TPM2_DATA: u8 = 0xda;
+pub const TPM2_END: u8 = 0x36;
+
+const ADA_MAGIC: &[u8; 3] = b"Ada";
+
+/// Longest pause between the bytes of a frame before it is given up on
+pub const BYTE_TIMEOUT_MS: u32 = 100;
+
+/// Header of an Adalight frame of `led_count` LEDs, at least one
+pub fn adalight_header(led_count: u16) -> [u8; 6] {
+    let [high, low] = led_count.saturating_sub(1).to_be_bytes();
+    [ADA_MAGIC[0], ADA_MAGIC[1], ADA_MAGIC[2], high, low, high ^ low ^ 0x55]
+}
+
+/// Header of a TPM2 data frame with `size` payload bytes, the payload is
+/// followed by `TPM2_END`
+pub fn tpm2_header(size: u16) -> [u8; 4] {
+    let [high, low] = size.to_be_bytes();
+    [TPM2_START, TPM2_DATA, high, low]
+}
+
+/// Console text released by one byte: the byte itself, after the start
+/// of an `Ada` header that turned out not to be one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Text {
+    bytes: [u8; ADA_MAGIC.len()],
+    len: u8,
+}
+
+impl Text {
+    const fn new() -> Self {
+        Self {
+            bytes: [0; ADA_MAGIC.len()],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.len as usize] = byte;
+        self.len += 1;
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+/// What a byte turned out to be
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decoded {
+    /// Not part of a frame, for the console
+    Text(Text),
+    /// Part of a frame still arriving, or a bad frame being dropped
+    Pending,
+    /// A whole frame arrived, see `StreamDecoder::frame`
+    Frame,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Idle,
+    /// Matched this many bytes of `Ada`
+    AdaMagic(u8),
+    AdaCountHigh,
+    AdaCountLow(u8),
+    AdaChecksum(u16),
+    Tpm2Type,
+    Tpm2SizeHigh(bool),
+    Tpm2SizeLow(bool, u8),
+    Data,
+    /// Payload of a TPM2 packet that isn't a frame
+    Skip,
+    Tpm2End(bool),
+}
+
+pub struct StreamDecoder {
+    state: State,
+    frame: [RGB8; NUM_BLADES],
+    incoming: [RGB8; NUM_BLADES],
+    /// Payload bytes still to come
+    remaining: usize,
+    /// Payload bytes received so far
+    received: usize,
+    /// The frame needs a TPM2 end byte
+    tpm2: bool,
+    last_byte: Option<Instant<u32, 1, 1000>>,
+}
+
+impl StreamDecoder {
+    pub const fn new() -> Self {
+        Self {
+            state: State::Idle,
+            frame: [RGB8 { r: 0, g: 0, b: 0 }; NUM_BLADES],
+            incoming: [RGB8 { r: 0, g: 0, b: 0 }; NUM_BLADES],
+            remaining: 0,
+            received: 0,
+            tpm2: false,
+            last_byte: None,
+        }
+    }
+
+    /// The last complete frame, one color per blade
+    pub fn frame(&self) -> &[RGB8; NUM_BLADES] {
+        &self.frame
+    }
+
+    /// Takes the next byte from the serial port, received at `now`
+    pub fn push(&mut self, byte: u8, now: Instant<u32, 1, 1000>) -> Decoded {
+        let stalled = self
+            .last_byte
+            .is_some_and(|last| (now - last).to_millis() >= BYTE_TIMEOUT_MS);
+        self.last_byte = Some(now);
+        if stalled {
+            return self.resync(byte);
+        }
+
+        match self.state {
+            State::Idle => return self.resync(byte),
+            State::AdaMagic(matched) => {
+                if byte != ADA_MAGIC[matched as usize] {
+                    return self.resync(byte);
+                }
+                self.state = if matched as usize + 1 == ADA_MAGIC.len() {
+                    State::AdaCountHigh
+                } else {
+                    State::AdaMagic(matched + 1)
+                };
+            }
+            State::AdaCountHigh => self.state = State::AdaCountLow(byte),
+            State::AdaCountLow(high) => self.state = State::AdaChecksum(u16::from_be_bytes([high, byte])),
+            State::AdaChecksum(count) => {
+                let [high, low] = count.to_be_bytes();
+                if byte == high ^ low ^ 0x55 {
+                    self.start_data((count as usize + 1) * 3, false);
+                } else {
+                    self.state = State::Idle;
+                }
+            }
+            State::Tpm2Type => self.state = State::Tpm2SizeHigh(byte == TPM2_DATA),
+            State::Tpm2SizeHigh(data) => self.state = State::Tpm2SizeLow(data, byte),
+            State::Tpm2SizeLow(data, high) => {
+                let size = u16::from_be_bytes([high, byte]) as usize;
+                if data {
+                    self.start_data(size, true);
+                    if size == 0 {
+                        self.state = State::Tpm2End(true);
+                    }
+                } else {
+                    self.remaining = size;
+                    self.state = if size == 0 { State::Tpm2End(false) } else { State::Skip };
+                }
+            }
+            State::Data => {
+                let led = self.received / 3;
+                if led < NUM_BLADES {
+                    let color = &mut self.incoming[led];
+                    match self.received % 3 {
+                        0 => color.r = byte,
+                        1 => color.g = byte,
+                        _ => color.b = byte,
+                    }
+                }
+                self.received += 1;
+                self.remaining -= 1;
+
+                if self.remaining == 0 {
+                    if self.tpm2 {
+                        self.state = State::Tpm2End(true);
+                    } else {
+                        self.state = State::Idle;
+                        return self.finish();
+                    }
+                }
+            }
+            State::Skip => {
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.state = State::Tpm2End(false);
+                }
+            }
+            State::Tpm2End(data) => {
+                self.state = State::Idle;
+                if data && byte == TPM2_END {
+                    return self.finish();
+                }
+            }
+        }
+
+        Decoded::Pending
+    }
+
+    /// Drops any partial frame and looks at `byte` afresh. The start of
+    /// an `Ada` header is handed back as the console text it really was.
+    fn resync(&mut self, byte: u8) -> Decoded {
+        let mut text = Text::new();
+        if let State::AdaMagic(matched) = self.state {
+            for &held in &ADA_MAGIC[..matched as usize] {
+                text.push(held);
+            }
+        }
+
+        self.state = match byte {
+            b'A' => State::AdaMagic(1),
+            TPM2_START => State::Tpm2Type,
+            _ => {
+                text.push(byte);
+                State::Idle
+            }
+        };
+
+        if text.len == 0 {
+            Decoded::Pending
+        } else {
+            Decoded::Text(text)
+        }
+    }
+
+    fn start_data(&mut self, size: usize, tpm2: bool) {
+        self.incoming = [RGB8::default(); NUM_BLADES];
+        self.received = 0;
+        self.remaining = size;
+        self.tpm2 = tpm2;
+        self.state = State::Data;
+    }
+
+    fn finish(&mut self) -> Decoded {
+        self.frame = self.incoming;
+        Decoded::Frame
+    }
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a stream is running, it stops once frames stop arriving
+pub struct StreamTimeout {
+    timeout_ms: u32,
+    last_frame: Option<Instant<u32, 1, 1000>>,
+}
+
+/// Result of `StreamTimeout::poll`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamState {
+    /// No stream, the effects are running
+    Idle,
+    /// Frames are arriving, the effects are paused
+    Streaming,
+    /// The stream just timed out, the effects should take over again
+    Stopped,
+}
+
+impl StreamTimeout {
+    pub const fn new(timeout_ms: u32) -> Self {
+        Self {
+            timeout_ms,
+            last_frame: None,
+        }
+    }
+
+    pub fn frame_received(&mut self, now: Instant<u32, 1, 1000>) {
+        self.last_frame = Some(now);
+    }
+
+    pub fn poll(&mut self, now: Instant<u32, 1, 1000>) -> StreamState {
+        let Some(last_frame) = self.last_frame else {
+            return StreamState::Idle;
+        };
+
+        if (now - last_frame).to_millis() < self.timeout_ms {
+            return StreamState::Streaming;
+        }
+
+        self.last_frame = None;
+        StreamState::Stopped
+    }
+}
@@ -0,0 +1,116 @@
+//! WS2812 bitstream encoding for an SPI MOSI line clocked at 3MHz
+//!
+//! Each data bit becomes four SPI bits (`1000` for 0, `1110` for 1), so an
+//! LED takes 12 bytes. Rendering the stream up front lets the firmware
+//! clock it out by DMA while the next frame is computed.
+//!
+//! SK6812 RGBW strips use the same timing with a fourth byte per LED.
+
+use smart_leds::RGB8;
+
+use crate::bitstream::Bitstream;
+use crate::output::RGBW8;
+
+/// Bytes per RGB LED, 24 data bits at 4 SPI bits each
+pub const BYTES_PER_LED: usize = 12;
+
+/// Bytes per RGBW LED, 32 data bits
+pub const BYTES_PER_RGBW_LED: usize = 16;
+
+/// Low bytes after the data, long enough to latch the LEDs
+pub const RESET_BYTES: usize = 30;
+
+/// Two data bits per byte
+const PATTERNS: [u8; 4] = [0b1000_1000, 0b1000_1110, 0b1110_1000, 0b1110_1110];
+
+/// Colors the encoder can put on the wire
+pub trait WireColor: Copy {
+    const BYTES_PER_LED: usize;
+
+    /// Channel bytes in the order they are sent, only the first
+    /// `BYTES_PER_LED / 4` are used
+    fn wire_bytes(&self) -> [u8; 4];
+}
+
+/// WS2812, GRB
+impl WireColor for RGB8 {
+    const BYTES_PER_LED: usize = BYTES_PER_LED;
+
+    fn wire_bytes(&self) -> [u8; 4] {
+        [self.g, self.r, self.b, 0]
+    }
+}
+
+/// SK6812 RGBW, GRBW
+impl WireColor for RGBW8 {
+    const BYTES_PER_LED: usize = BYTES_PER_RGBW_LED;
+
+    fn wire_bytes(&self) -> [u8; 4] {
+        [self.g, self.r, self.b, self.a.0]
+    }
+}
+
+/// Size of the prerendered bitstream for `leds` RGB LEDs
+pub const fn buffer_len(leds: usize) -> usize {
+    buffer_len_for::<RGB8>(leds)
+}
+
+/// Size of the prerendered bitstream for `leds` LEDs of color type `C`
+pub const fn buffer_len_for<C: WireColor>(leds: usize) -> usize {
+    (leds * C::BYTES_PER_LED) + RESET_BYTES
+}
+
+/// Renders `leds` into `buffer` in the order the LEDs expect
+///
+/// The rest of the buffer is cleared so the strip latches after the
+/// last LED. Returns the number of LEDs written.
+pub fn encode<C, I>(leds: I, buffer: &mut [u8]) -> Result<usize, &'static str>
+where
+    C: WireColor,
+    I: IntoIterator<Item = C>,
+{
+    let bytes_per_led = C::BYTES_PER_LED;
+
+    // Always leave room for the reset gap
+    let data_len = buffer.len().saturating_sub(RESET_BYTES);
+    let mut count = 0;
+
+    for led in leds {
+        let start = count * bytes_per_led;
+        if start + bytes_per_led > data_len {
+            return Err("too many LEDs for the buffer")
+        }
+        let out = &mut buffer[start..start + bytes_per_led];
+
+        for (i, byte) in led.wire_bytes().iter().take(bytes_per_led / 4).enumerate() {
+            for pair in 0..4 {
+                let bits = (byte >> (6 - pair * 2)) & 0b11;
+                out[i * 4 + pair] = PATTERNS[bits as usize];
+            }
+        }
+
+        count += 1;
+    }
+
+    buffer[count * bytes_per_led..].fill(0);
+
+    Ok(count)
+}
+
+impl Bitstream for RGB8 {
+    fn encode<I>(leds: I, buffer: &mut [u8]) -> Result<usize, &'static str>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        encode(leds, buffer)
+    }
+}
+
+impl Bitstream for RGBW8 {
+    fn encode<I>(leds: I, buffer: &mut [u8]) -> Result<usize, &'static str>
+    where
+        I: IntoIterator<Item = Self>,
+    {
+        encode(leds, buffer)
+    }
+}
@@ -0,0 +1,50 @@
+use juicy_core::apa102::{buffer_len, end_bytes, Apa102Color, BYTES_PER_LED, START_BYTES};
+use juicy_core::bitstream::Bitstream;
+use juicy_core::output::{ColorOrder, Gamma, OutputStage, Pixel};
+use smart_leds::RGB8;
+
+#[test]
+fn frames_have_start_leds_and_end() {
+    let mut buffer = [0xffu8; buffer_len(2)];
+    let leds = [
+        Apa102Color { color: RGB8::new(1, 2, 3), brightness: 31 },
+        Apa102Color { color: RGB8::new(4, 5, 6), brightness: 1 },
+    ];
+    assert_eq!(Apa102Color::encode(leds, &mut buffer), Ok(2));
+
+    assert_eq!(buffer[..START_BYTES], [0; 4]);
+    assert_eq!(buffer[4..8], [0xff, 3, 2, 1]);
+    assert_eq!(buffer[8..12], [0xe1, 6, 5, 4]);
+    assert!(buffer[START_BYTES + 2 * BYTES_PER_LED..].iter().all(|b| *b == 0));
+    assert_eq!(buffer.len(), START_BYTES + 2 * BYTES_PER_LED + end_bytes(2));
+}
+
+#[test]
+fn too_many_leds_is_an_error() {
+    let mut buffer = [0u8; buffer_len(1)];
+    let leds = [Apa102Color::default(); 2];
+    assert!(Apa102Color::encode(leds, &mut buffer).is_err());
+}
+
+#[test]
+fn bright_colors_use_full_global_brightness() {
+    let stage = OutputStage::new().with_color_order(ColorOrder::BGR, ColorOrder::BGR);
+    let led = Apa102Color::render(RGB8::new(255, 128, 0), 0, &stage);
+    assert_eq!(led, Apa102Color { color: RGB8::new(255, 128, 0), brightness: 31 });
+}
+
+/// Gamma 2.2 flattens 8-bit levels below about 20 to zero, the global
+/// brightness keeps them apart
+#[test]
+fn dim_colors_keep_detail_through_gamma() {
+    let stage = OutputStage::new()
+        .with_gamma(Gamma::Gamma22)
+        .with_color_order(ColorOrder::BGR, ColorOrder::BGR);
+    assert_eq!(Gamma::Gamma22.table()[10], 0);
+
+    let dim = Apa102Color::render(RGB8::new(10, 0, 0), 0, &stage);
+    let dimmer = Apa102Color::render(RGB8::new(8, 0, 0), 0, &stage);
+    assert_eq!(dim.brightness, 1);
+    assert!(dim.color.r > dimmer.color.r);
+    assert!(dimmer.color.r > 0);
+}
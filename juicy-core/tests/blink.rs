@@ -0,0 +1,93 @@
+mod common;
+
+use common::{FakeClock, MockStrip};
+use juicy_core::blink::{Blink, BLINK_MSEC};
+use juicy_core::lights::BladeLights;
+use smart_leds::RGB8;
+
+#[test]
+fn default_pattern_is_on_then_off_for_blink_msec() {
+    let blink = Blink::DEFAULT;
+    assert_eq!(blink.level(0), 255);
+    assert_eq!(blink.level(BLINK_MSEC - 1), 255);
+    assert_eq!(blink.level(BLINK_MSEC + 1), 0);
+    assert_eq!(blink.level(2 * BLINK_MSEC), 255);
+}
+
+#[test]
+fn duty_sets_the_lit_part_of_the_period() {
+    let blink = Blink::square(1000, 64);
+    let lit = (0..1000).filter(|ms| blink.level(*ms) == 255).count();
+    assert!((250..=252).contains(&lit), "{}", lit);
+
+    let always = Blink::square(1000, 255);
+    assert!((0..1000).all(|ms| always.level(ms) == 255));
+}
+
+#[test]
+fn phase_shifts_the_pattern_later() {
+    let blink = Blink::square(400, 128);
+    let shifted = blink.with_phase(100);
+    for ms in 0..800 {
+        assert_eq!(shifted.level(ms + 100), blink.level(ms));
+    }
+
+    // Phases past a whole period wrap around
+    assert_eq!(blink.with_phase(500).level(500), blink.level(400));
+}
+
+#[test]
+fn breathe_fades_up_and_down() {
+    let blink = Blink::breathe(1000);
+    assert!(blink.level(0) < 5);
+    assert!(blink.level(500) > 250);
+    assert!(blink.level(999) < 5);
+    assert!(blink.level(250) > 100 && blink.level(250) < 150);
+    assert!(blink.level(100) < blink.level(200));
+    assert!(blink.level(800) < blink.level(700));
+}
+
+#[test]
+fn blades_blink_with_their_own_patterns() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    let red = RGB8::new(200, 0, 0);
+
+    lights.set_blade(0, red, false).unwrap();
+    lights.set_blade(1, red, false).unwrap();
+    lights.set_blade(2, red, false).unwrap();
+    lights.set_blink(0, Some(Blink::square(100, 128))).unwrap();
+    lights.set_blink(1, Some(Blink::square(100, 128).with_phase(50))).unwrap();
+    lights.set_blink(2, Some(Blink::breathe(100))).unwrap();
+
+    clock.advance(25);
+    lights.refresh(false, &clock).unwrap();
+    let frame = lights.sink().frames.last().unwrap().clone();
+    assert_eq!(frame[0], red);
+    assert_eq!(frame[1], RGB8::default());
+    assert!(frame[2].r > 80 && frame[2].r < 120);
+
+    clock.advance(50);
+    lights.refresh(false, &clock).unwrap();
+    let frame = lights.sink().frames.last().unwrap();
+    assert_eq!(frame[0], RGB8::default());
+    assert_eq!(frame[1], red);
+}
+
+#[test]
+fn steady_frames_are_not_rewritten() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    lights.set_blink(0, Some(Blink::square(1000, 128))).unwrap();
+
+    lights.refresh(false, &clock).unwrap();
+    for _ in 0..10 {
+        clock.advance(10);
+        lights.refresh(false, &clock).unwrap();
+    }
+    assert_eq!(lights.sink().frames.len(), 1);
+
+    clock.advance(500);
+    lights.refresh(false, &clock).unwrap();
+    assert_eq!(lights.sink().frames.len(), 2);
+}
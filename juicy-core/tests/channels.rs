@@ -0,0 +1,77 @@
+mod common;
+
+use common::MockStrip;
+use juicy_core::channels::{Channel, ChannelError, MultiChannel};
+use smart_leds::{SmartLedsWrite, RGB8};
+
+/// Strip that always fails to write
+struct BrokenStrip;
+
+impl SmartLedsWrite for BrokenStrip {
+    type Error = ();
+    type Color = RGB8;
+
+    fn write<T, I>(&mut self, _iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        Err(())
+    }
+}
+
+fn leds(count: u8) -> Vec<RGB8> {
+    (0..count).map(|i| RGB8::new(i, 0, 0)).collect()
+}
+
+#[test]
+fn leds_are_split_across_channels_in_order() {
+    let mut strips = MultiChannel::new((
+        Channel::new(MockStrip::new(), 3),
+        Channel::new(MockStrip::new(), 0),
+        Channel::new(MockStrip::new(), 2),
+    ));
+    assert_eq!(strips.led_count(), 5);
+
+    strips.write(leds(5).into_iter()).unwrap();
+
+    let (first, unused, last) = strips.channels();
+    assert_eq!(first.sink().frames, [leds(3)]);
+    assert!(unused.sink().frames.is_empty());
+    assert_eq!(last.sink().frames, [vec![RGB8::new(3, 0, 0), RGB8::new(4, 0, 0)]]);
+}
+
+#[test]
+fn failing_channel_is_reported() {
+    let mut strips = MultiChannel::new((
+        Channel::new(MockStrip::new(), 2),
+        Channel::new(BrokenStrip, 2),
+    ));
+
+    assert_eq!(strips.write(leds(4).into_iter()), Err(ChannelError { channel: 1, cause: () }));
+}
+
+#[test]
+fn channels_after_a_failing_one_are_still_written() {
+    let mut strips = MultiChannel::new((
+        Channel::new(MockStrip::new(), 2),
+        Channel::new(BrokenStrip, 2),
+        Channel::new(MockStrip::new(), 2),
+    ));
+
+    assert_eq!(strips.write(leds(6).into_iter()), Err(ChannelError { channel: 1, cause: () }));
+
+    let (first, _, last) = strips.channels();
+    assert_eq!(first.sink().frames, [leds(2)]);
+    assert_eq!(last.sink().frames, [vec![RGB8::new(4, 0, 0), RGB8::new(5, 0, 0)]]);
+}
+
+#[test]
+fn first_failure_is_reported() {
+    let mut strips = MultiChannel::new((
+        Channel::new(BrokenStrip, 1),
+        Channel::new(BrokenStrip, 1),
+    ));
+
+    assert_eq!(strips.write(leds(2).into_iter()), Err(ChannelError { channel: 0, cause: () }));
+}
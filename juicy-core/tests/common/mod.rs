@@ -0,0 +1,99 @@
+#![allow(dead_code)]
+
+use std::cell::Cell;
+use std::vec::Vec;
+
+use fugit::Instant;
+use juicy_core::clock::Clock;
+use juicy_core::frame::{BladeSink, NUM_BLADES};
+use juicy_core::output::RGBW8;
+use smart_leds::{SmartLedsWrite, RGB8};
+
+/// Clock the tests move by hand
+pub struct FakeClock {
+    now_ms: Cell<u32>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self { now_ms: Cell::new(0) }
+    }
+
+    pub fn advance(&self, ms: u32) {
+        self.now_ms.set(self.now_ms.get() + ms);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant<u32, 1, 1000> {
+        Instant::<u32, 1, 1000>::from_ticks(self.now_ms.get())
+    }
+}
+
+/// Blade sink that keeps the last color shown on each blade
+pub struct MockBlades {
+    pub blades: [RGB8; NUM_BLADES],
+}
+
+impl MockBlades {
+    pub fn new() -> Self {
+        Self { blades: [RGB8::default(); NUM_BLADES] }
+    }
+}
+
+impl BladeSink for MockBlades {
+    fn set_blade(&mut self, blade: u8, color: RGB8, _blink: bool) -> Result<(), &'static str> {
+        *self.blades.get_mut(blade as usize).ok_or("blade out of range")? = color;
+        Ok(())
+    }
+}
+
+/// LED strip that records every frame written to it
+pub struct MockStrip {
+    pub frames: Vec<Vec<RGB8>>,
+}
+
+impl MockStrip {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+}
+
+impl SmartLedsWrite for MockStrip {
+    type Error = ();
+    type Color = RGB8;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        self.frames.push(iterator.map(|c| c.into()).collect());
+        Ok(())
+    }
+}
+
+/// RGBW version of `MockStrip`
+pub struct MockRgbwStrip {
+    pub frames: Vec<Vec<RGBW8>>,
+}
+
+impl MockRgbwStrip {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+}
+
+impl SmartLedsWrite for MockRgbwStrip {
+    type Error = ();
+    type Color = RGBW8;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        self.frames.push(iterator.map(|c| c.into()).collect());
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use common::{FakeClock, MockStrip};
+use juicy_core::console::{execute, parse, Command, LineBuffer, MAX_LINE};
+use juicy_core::effects::{EffectManager, EFFECT_REGISTRY};
+use juicy_core::lights::BladeLights;
+use juicy_core::playlist::PlaylistOrder;
+use smart_leds::RGB8;
+
+fn run(line: &str, manager: &mut EffectManager, clock: &FakeClock) -> String {
+    let mut reply = String::new();
+    execute(parse(line).unwrap(), manager, clock, &mut reply).unwrap();
+    reply
+}
+
+#[test]
+fn parses_every_command() {
+    assert_eq!(parse("effect spiral"), Ok(Command::Effect("spiral")));
+    assert_eq!(parse("brightness 128"), Ok(Command::Brightness(128)));
+    assert_eq!(parse("duration 30"), Ok(Command::Duration(30)));
+    assert_eq!(parse("status"), Ok(Command::Status));
+    assert_eq!(parse("list"), Ok(Command::List));
+    assert_eq!(
+        parse("blade 3 255 0 10"),
+        Ok(Command::Blade { blade: 3, color: RGB8::new(255, 0, 10) })
+    );
+    assert_eq!(parse("blade clear"), Ok(Command::ClearBlades));
+    assert_eq!(parse("help"), Ok(Command::Help));
+    assert_eq!(parse("params 20"), Ok(Command::Params { delay_ms: 20, param: None }));
+    assert_eq!(parse("params 20 7"), Ok(Command::Params { delay_ms: 20, param: Some(7) }));
+    assert_eq!(parse("playlist"), Ok(Command::Playlist));
+    assert_eq!(parse("playlist clear"), Ok(Command::PlaylistClear));
+    assert_eq!(
+        parse("playlist add fire 30"),
+        Ok(Command::PlaylistAdd { name: "fire", duration_sec: 30 })
+    );
+    assert_eq!(parse("playlist order shuffle"), Ok(Command::PlaylistOrder(PlaylistOrder::Shuffle)));
+    assert_eq!(parse("playlist play"), Ok(Command::PlaylistPlay));
+}
+
+#[test]
+fn commands_format_as_the_lines_they_parse_from() {
+    let commands = [
+        Command::Effect("spiral"),
+        Command::Brightness(128),
+        Command::Duration(30),
+        Command::Params { delay_ms: 20, param: None },
+        Command::Params { delay_ms: 20, param: Some(7) },
+        Command::Status,
+        Command::List,
+        Command::Blade { blade: 3, color: RGB8::new(255, 0, 10) },
+        Command::ClearBlades,
+        Command::Playlist,
+        Command::PlaylistClear,
+        Command::PlaylistAdd { name: "fire", duration_sec: 30 },
+        Command::PlaylistOrder(PlaylistOrder::Sequential),
+        Command::PlaylistOrder(PlaylistOrder::Shuffle),
+        Command::PlaylistOrder(PlaylistOrder::WeightedRandom),
+        Command::PlaylistPlay,
+        Command::Help,
+    ];
+
+    for command in commands {
+        let line = command.to_string();
+        assert!(line.len() <= MAX_LINE, "{}", line);
+        assert_eq!(parse(&line), Ok(command));
+    }
+}
+
+#[test]
+fn whitespace_is_ignored() {
+    assert_eq!(parse("  brightness\t 7  "), Ok(Command::Brightness(7)));
+}
+
+#[test]
+fn bad_lines_are_errors() {
+    assert!(parse("").is_err());
+    assert!(parse("dance").is_err());
+    assert!(parse("brightness").is_err());
+    assert!(parse("brightness 256").is_err());
+    assert!(parse("duration -1").is_err());
+    assert!(parse("blade 1 2 3").is_err());
+    assert!(parse("status now").is_err());
+    assert!(parse("params").is_err());
+    assert!(parse("playlist add fire").is_err());
+    assert!(parse("playlist order backwards").is_err());
+}
+
+#[test]
+fn zero_durations_are_rejected() {
+    assert_eq!(parse("duration 0"), Err("usage: duration <seconds>"));
+    assert_eq!(parse("playlist add fire 0"), Err("usage: playlist add <name> <seconds>"));
+    assert_eq!(parse("duration 1"), Ok(Command::Duration(1)));
+}
+
+#[test]
+fn effect_brightness_and_duration_change_the_manager() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+
+    assert_eq!(run("effect spiral", &mut manager, &clock), "ok\n");
+    assert_eq!(manager.effect_name(), "spiral");
+    assert!(run("effect nope", &mut manager, &clock).starts_with("error"));
+
+    run("brightness 40", &mut manager, &clock);
+    assert_eq!(manager.brightness(), 40);
+
+    run("duration 5", &mut manager, &clock);
+    assert_eq!(manager.effect_duration(), 5);
+
+    let status = run("status", &mut manager, &clock);
+    assert!(status.contains("effect: spiral"));
+    assert!(status.contains("brightness: 40"));
+}
+
+#[test]
+fn list_shows_every_effect() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+
+    let list = run("list", &mut manager, &clock);
+    assert_eq!(list.lines().count(), EFFECT_REGISTRY.len());
+    for info in EFFECT_REGISTRY {
+        assert!(list.contains(info.name));
+    }
+}
+
+#[test]
+fn params_restart_the_current_effect() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+    run("effect spiral", &mut manager, &clock);
+    clock.advance(3000);
+
+    assert_eq!(run("params 10 3", &mut manager, &clock), "ok\n");
+    assert_eq!(manager.effect_name(), "spiral");
+    assert_eq!(manager.effect_elapsed(&clock), 0);
+}
+
+#[test]
+fn playlist_is_replaced_and_played() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+
+    run("playlist clear", &mut manager, &clock);
+    assert_eq!(run("playlist add fire 5", &mut manager, &clock), "ok\n");
+    assert_eq!(run("playlist add spiral 7", &mut manager, &clock), "ok\n");
+    assert!(run("playlist add nope 7", &mut manager, &clock).starts_with("error"));
+    run("playlist order shuffle", &mut manager, &clock);
+
+    assert_eq!(
+        run("playlist", &mut manager, &clock),
+        "order: shuffle\n0 fire 5s\n1 spiral 7s\n"
+    );
+
+    run("playlist order sequential", &mut manager, &clock);
+    run("playlist play", &mut manager, &clock);
+    assert_eq!(manager.effect_name(), "fire");
+    assert_eq!(manager.effect_duration(), 5);
+}
+
+#[test]
+fn blade_holds_a_color_until_cleared() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    let color = RGB8::new(1, 2, 3);
+
+    run("blade 4 1 2 3", &mut manager, &clock);
+    assert!(manager.update(&mut lights, &clock));
+    assert_eq!(lights.current_leds().nth(4), Some(color));
+
+    run("blade clear", &mut manager, &clock);
+    assert!(manager.update(&mut lights, &clock));
+    assert_ne!(lights.current_leds().nth(4), Some(color));
+
+    assert!(run("blade 99 1 2 3", &mut manager, &clock).starts_with("error"));
+}
+
+#[test]
+fn line_buffer_splits_on_cr_and_lf() {
+    let mut buffer = LineBuffer::new();
+    let mut lines = Vec::new();
+
+    for byte in b"list\r\nstatus\n\neffect fire\r" {
+        if let Some(line) = buffer.push(*byte) {
+            lines.push(line.map(String::from));
+        }
+    }
+
+    assert_eq!(lines, [Ok("list".into()), Ok("status".into()), Ok("effect fire".into())]);
+}
+
+#[test]
+fn long_lines_are_dropped() {
+    let mut buffer = LineBuffer::new();
+    for _ in 0..MAX_LINE + 1 {
+        assert!(buffer.push(b'x').is_none());
+    }
+    assert_eq!(buffer.push(b'\n'), Some(Err("line too long")));
+
+    for byte in b"list" {
+        buffer.push(*byte);
+    }
+    assert_eq!(buffer.push(b'\n'), Some(Ok("list")));
+}
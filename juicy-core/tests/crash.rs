@@ -0,0 +1,50 @@
+use juicy_core::crash::{CrashRecord, FILE_LEN, MESSAGE_LEN};
+
+#[test]
+fn recorded_crash_is_reported() {
+    let mut record = CrashRecord::new();
+    assert!(!record.is_valid());
+
+    record.record(&format_args!("index {} out of range", 40), "src/light_ports.rs", 120, 9);
+
+    assert!(record.is_valid());
+    assert_eq!(record.message(), "index 40 out of range");
+    assert_eq!(record.file(), "src/light_ports.rs");
+    assert_eq!(record.to_string(), "panicked at src/light_ports.rs:120:9: index 40 out of range");
+}
+
+#[test]
+fn cleared_record_is_not_reported_again() {
+    let mut record = CrashRecord::new();
+    record.record(&"boom", "src/main.rs", 1, 1);
+
+    record.clear();
+
+    assert!(!record.is_valid());
+}
+
+#[test]
+fn changed_bytes_invalidate_the_record() {
+    let mut record = CrashRecord::new();
+    record.record(&"boom", "src/main.rs", 1, 1);
+
+    // Stands in for RAM that lost its contents while powered off
+    let bytes: &mut [u8; core::mem::size_of::<CrashRecord>()] =
+        unsafe { &mut *(&mut record as *mut CrashRecord).cast() };
+    bytes[20] ^= 0x01;
+    assert!(!record.is_valid());
+}
+
+#[test]
+fn long_text_is_truncated_on_character_boundaries() {
+    let message = "é".repeat(MESSAGE_LEN);
+    let file = format!("{}/src/main.rs", "ü".repeat(FILE_LEN));
+    let mut record = CrashRecord::new();
+
+    record.record(&message, &file, 7, 3);
+
+    assert!(record.is_valid());
+    assert_eq!(record.message(), "é".repeat(MESSAGE_LEN / 2));
+    assert!(record.file().ends_with("/src/main.rs"));
+    assert!(record.file().len() <= FILE_LEN);
+}
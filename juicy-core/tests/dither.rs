@@ -0,0 +1,60 @@
+mod common;
+
+use common::{FakeClock, MockStrip};
+use juicy_core::lights::BladeLights;
+use juicy_core::output::{DitherError, Gamma, OutputStage, Pixel};
+use smart_leds::RGB8;
+
+/// Averages `frames` dithered renders of `color`, in 1/256ths
+fn average(color: RGB8, output: &OutputStage, frames: u32) -> u32 {
+    let mut error: DitherError = [0; 4];
+    let total: u32 = (0..frames)
+        .map(|_| RGB8::render_dithered(color, 0, output, &mut error).r as u32)
+        .sum();
+    (total * 256) / frames
+}
+
+#[test]
+fn dithering_averages_to_the_16_bit_level() {
+    let output = OutputStage::new().with_gamma(Gamma::Gamma22);
+
+    for level in [5u8, 20, 40, 100] {
+        let exact = Gamma::Gamma22.table16()[level as usize] as u32;
+        let dithered = average(RGB8::new(level, 0, 0), &output, 256);
+        assert!(dithered.abs_diff(exact) <= 1, "level {}: {} vs {}", level, dithered, exact);
+    }
+}
+
+/// Levels that gamma collapses to the same 8-bit value stay apart
+#[test]
+fn neighbouring_dim_levels_stay_distinct() {
+    let output = OutputStage::new().with_gamma(Gamma::Gamma22);
+    let table = Gamma::Gamma22.table();
+    assert_eq!(table[30], table[31]);
+
+    assert!(average(RGB8::new(30, 0, 0), &output, 256) < average(RGB8::new(31, 0, 0), &output, 256));
+}
+
+#[test]
+fn lights_write_every_refresh_while_dithering() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    lights.set_output(OutputStage::new().with_gamma(Gamma::Gamma22));
+    lights.set_blade(0, RGB8::new(30, 0, 0), false).unwrap();
+
+    // The first refresh always writes
+    lights.refresh(false, &clock).unwrap();
+    clock.advance(1);
+    lights.refresh(false, &clock).unwrap();
+    assert_eq!(lights.sink().frames.len(), 1);
+
+    lights.set_dithering(true);
+    for _ in 0..16 {
+        lights.refresh(false, &clock).unwrap();
+    }
+
+    let frames = &lights.sink().frames;
+    assert_eq!(frames.len(), 17);
+    let seen: Vec<u8> = frames[1..].iter().map(|frame| frame[0].r).collect();
+    assert!(seen.iter().any(|r| *r != seen[0]), "{:?}", seen);
+}
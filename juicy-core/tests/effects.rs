@@ -0,0 +1,228 @@
+mod common;
+
+use common::{FakeClock, MockBlades, MockStrip};
+use juicy_core::effects::{find_effect, EffectManager, Transition, EFFECT_REGISTRY};
+use juicy_core::frame::{FrameBuffer, NUM_BLADES};
+use juicy_core::lights::BladeLights;
+use juicy_core::playlist::{Playlist, PlaylistEntry, PlaylistOrder};
+use juicy_core::rng::DEFAULT_SEED;
+use smart_leds::RGB8;
+
+#[test]
+fn registry_names_are_unique() {
+    for (i, info) in EFFECT_REGISTRY.iter().enumerate() {
+        assert_eq!(find_effect(info.name), Some(i));
+    }
+    assert_eq!(find_effect("no_such_effect"), None);
+}
+
+#[test]
+fn every_effect_waits_for_its_delay() {
+    let clock = FakeClock::new();
+
+    for info in EFFECT_REGISTRY {
+        let params = (info.default_params)();
+        let mut effect = (info.build)(&params, DEFAULT_SEED);
+        let mut frame = FrameBuffer::new();
+
+        clock.advance(params.delay_ms);
+        assert!(effect.update(&mut frame, &clock), "{}", info.name);
+        assert!(!effect.update(&mut frame, &clock), "{}", info.name);
+        clock.advance(params.delay_ms);
+        assert!(effect.update(&mut frame, &clock), "{}", info.name);
+    }
+}
+
+#[test]
+fn every_effect_lights_something() {
+    let clock = FakeClock::new();
+
+    for info in EFFECT_REGISTRY {
+        let params = (info.default_params)();
+        let mut effect = (info.build)(&params, DEFAULT_SEED);
+        let mut frame = FrameBuffer::new();
+        let mut lit = false;
+
+        for _ in 0..500 {
+            clock.advance(params.delay_ms);
+            effect.update(&mut frame, &clock);
+            lit |= (0..NUM_BLADES).any(|blade| frame.get_blade(blade) != RGB8::default());
+        }
+
+        assert!(lit, "{} never lit a blade", info.name);
+    }
+}
+
+#[test]
+fn manager_follows_the_playlist() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    let mut manager = EffectManager::new(&clock);
+    manager.set_transition(Transition::Cut, 0);
+
+    let mut playlist = Playlist::new(PlaylistOrder::Sequential);
+    playlist.push(PlaylistEntry::new(0, 100, 50, 1)).unwrap();
+    playlist.push(PlaylistEntry::new(1, 100, 50, 2)).unwrap();
+    manager.set_playlist(playlist, &clock);
+    assert_eq!(manager.effect_index(), 0);
+
+    for _ in 0..1000 {
+        clock.advance(1);
+        manager.update(&mut lights, &clock);
+    }
+    assert_eq!(manager.effect_index(), 1);
+
+    for _ in 0..2000 {
+        clock.advance(1);
+        manager.update(&mut lights, &clock);
+    }
+    assert_eq!(manager.effect_index(), 0);
+}
+
+/// Transition length that makes the progress the elapsed time / 10
+const TRANSITION_MS: u32 = 2550;
+
+/// Shows fire, then runs `transition` to spiral until `elapsed_ms` in and
+/// on to the end. Both effects render once and then hold still, so the
+/// blades are the blend alone. Returns the blades before, at `elapsed_ms`
+/// and after.
+fn run_transition(transition: Transition, elapsed_ms: u32) -> [[RGB8; NUM_BLADES]; 3] {
+    let clock = FakeClock::new();
+    let mut blades = MockBlades::new();
+    let mut manager = EffectManager::new(&clock);
+
+    // Effects first render once their delay is up, and not again until
+    // the next one
+    let delay_ms = 10_000;
+    clock.advance(delay_ms);
+
+    let mut playlist = Playlist::new(PlaylistOrder::Sequential);
+    playlist.push(PlaylistEntry::new(find_effect("fire").unwrap(), 255, delay_ms, 60)).unwrap();
+    playlist.push(PlaylistEntry::new(find_effect("spiral").unwrap(), 255, delay_ms, 60)).unwrap();
+    manager.set_transition(Transition::Cut, 0);
+    manager.set_playlist(playlist, &clock);
+    manager.update(&mut blades, &clock);
+    let from = blades.blades;
+
+    manager.set_transition(transition, TRANSITION_MS);
+    manager.next_effect(&clock);
+    manager.update(&mut blades, &clock);
+
+    clock.advance(elapsed_ms);
+    manager.update(&mut blades, &clock);
+    let during = blades.blades;
+
+    clock.advance(TRANSITION_MS - elapsed_ms);
+    manager.update(&mut blades, &clock);
+    let to = blades.blades;
+
+    assert_ne!(from, to, "the effects need to look different");
+    [from, during, to]
+}
+
+/// Channels of `color`, to compare them one by one
+fn channels(color: RGB8) -> [u8; 3] {
+    [color.r, color.g, color.b]
+}
+
+#[test]
+fn crossfade_blends_the_two_effects() {
+    let [from, midway, to] = run_transition(Transition::Crossfade, TRANSITION_MS / 2);
+
+    for blade in 0..NUM_BLADES {
+        let expected = channels(from[blade]).into_iter().zip(channels(to[blade]));
+        for (shown, (from, to)) in channels(midway[blade]).into_iter().zip(expected) {
+            let halfway = (from as i32 + to as i32) / 2;
+            assert!((shown as i32 - halfway).abs() <= 1, "blade {}: {} between {} and {}", blade, shown, from, to);
+        }
+    }
+}
+
+#[test]
+fn fade_through_black_is_dark_halfway() {
+    let [from, halfway, to] = run_transition(Transition::FadeThroughBlack, 1280);
+    assert!(halfway.iter().all(|&color| color == RGB8::default()));
+
+    // Outgoing colors only fade on the way down, incoming only grow on
+    // the way up
+    let [_, quarter, _] = run_transition(Transition::FadeThroughBlack, 640);
+    let [_, three_quarters, _] = run_transition(Transition::FadeThroughBlack, 1920);
+    for blade in 0..NUM_BLADES {
+        for (shown, from) in channels(quarter[blade]).into_iter().zip(channels(from[blade])) {
+            assert!(shown <= from && shown >= (from / 2).saturating_sub(1), "blade {}", blade);
+        }
+        for (shown, to) in channels(three_quarters[blade]).into_iter().zip(channels(to[blade])) {
+            assert!(shown <= to && shown >= (to / 2).saturating_sub(1), "blade {}", blade);
+        }
+    }
+}
+
+#[test]
+fn wipe_moves_an_edge_outwards() {
+    let [from, midway, to] = run_transition(Transition::Wipe, 1280);
+    let edge = 128 * NUM_BLADES / 255;
+    assert!(edge > 0 && edge < NUM_BLADES);
+
+    for blade in 0..NUM_BLADES {
+        let expected = if blade < edge { to[blade] } else { from[blade] };
+        assert_eq!(midway[blade], expected, "blade {}", blade);
+    }
+}
+
+#[test]
+fn select_effect_rejects_unknown_id() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+
+    assert!(manager.select_effect(EFFECT_REGISTRY.len(), &clock).is_err());
+    assert!(manager.select_effect(0, &clock).is_ok());
+    assert_eq!(manager.effect_name(), EFFECT_REGISTRY[0].name);
+}
+
+#[test]
+fn same_seed_gives_same_frames() {
+    let render = |seed: u32| {
+        let clock = FakeClock::new();
+        let mut frames = Vec::new();
+
+        for info in EFFECT_REGISTRY {
+            let params = (info.default_params)();
+            let mut effect = (info.build)(&params, seed);
+            let mut frame = FrameBuffer::new();
+
+            for _ in 0..100 {
+                clock.advance(params.delay_ms);
+                effect.update(&mut frame, &clock);
+                frames.extend((0..NUM_BLADES).map(|blade| frame.get_blade(blade)));
+            }
+        }
+        frames
+    };
+
+    assert!(render(1) == render(1));
+    assert!(render(1) != render(2));
+}
+
+#[test]
+fn reset_replays_the_same_frames() {
+    let clock = FakeClock::new();
+
+    for info in EFFECT_REGISTRY {
+        let params = (info.default_params)();
+        let mut effect = (info.build)(&params, 42);
+        let run = |effect: &mut juicy_core::effects::EffectSlot| {
+            let mut frame = FrameBuffer::new();
+            let mut frames = Vec::new();
+            for _ in 0..50 {
+                clock.advance(params.delay_ms);
+                effect.update(&mut frame, &clock);
+                frames.extend((0..NUM_BLADES).map(|blade| frame.get_blade(blade)));
+            }
+            frames
+        };
+
+        let first = run(&mut effect);
+        effect.reset();
+        assert!(first == run(&mut effect), "{}", info.name);
+    }
+}
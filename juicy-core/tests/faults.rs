@@ -0,0 +1,41 @@
+use juicy_core::faults::{FaultCounter, Recovery};
+
+#[test]
+fn failures_are_retried_until_the_limit() {
+    let mut faults = FaultCounter::new(3);
+
+    assert_eq!(faults.record_error(1u8), Recovery::Retry);
+    assert_eq!(faults.record_error(2u8), Recovery::Retry);
+    assert_eq!(faults.consecutive(), 2);
+    assert_eq!(faults.record_error(3u8), Recovery::Reinit);
+
+    assert_eq!(faults.errors(), 3);
+    assert_eq!(faults.reinits(), 1);
+    assert_eq!(faults.consecutive(), 0);
+    assert_eq!(faults.last_error(), Some(3));
+}
+
+#[test]
+fn success_resets_the_run_but_not_the_totals() {
+    let mut faults = FaultCounter::new(3);
+
+    faults.record_error(());
+    faults.record_error(());
+    faults.record_ok();
+    assert_eq!(faults.consecutive(), 0);
+
+    assert_eq!(faults.record_error(()), Recovery::Retry);
+    assert_eq!(faults.record_error(()), Recovery::Retry);
+    assert_eq!(faults.errors(), 4);
+    assert_eq!(faults.reinits(), 0);
+}
+
+#[test]
+fn summary_names_the_last_error() {
+    let mut faults = FaultCounter::new(2);
+    assert_eq!(faults.to_string(), "0 errors, 0 restarts");
+
+    faults.record_error("timeout");
+    faults.record_error("stalled");
+    assert_eq!(faults.to_string(), "2 errors, 1 restarts, last \"stalled\"");
+}
@@ -0,0 +1,32 @@
+use juicy_core::frame_stats::{FrameStats, FrameWindow};
+
+#[test]
+fn frames_over_budget_are_overruns() {
+    let mut stats = FrameStats::new(1000);
+    stats.record(200);
+    stats.record(1000);
+    stats.record(1500);
+    stats.record_missed(1);
+
+    assert_eq!(stats.frames(), 3);
+    assert_eq!(stats.overruns(), 1);
+    assert_eq!(stats.missed(), 1);
+}
+
+#[test]
+fn window_reports_average_max_and_load() {
+    let mut stats = FrameStats::new(1000);
+    assert_eq!(stats.window(), FrameWindow::default());
+
+    stats.record(100);
+    stats.record(300);
+    assert_eq!(
+        stats.window(),
+        FrameWindow { frames: 2, average: 200, max: 300, load_percent: 20 }
+    );
+
+    stats.reset_window();
+    stats.record(50);
+    assert_eq!(stats.window().max, 50);
+    assert_eq!(stats.frames(), 3);
+}
@@ -0,0 +1,55 @@
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::geometry::{angle_distance, ShellGeometry};
+
+#[test]
+fn height_rises_from_core_to_top() {
+    let geometry = ShellGeometry::default();
+    assert_eq!(geometry.position(0).height, 0);
+    assert_eq!(geometry.position(NUM_BLADES - 1).height, 255);
+
+    for blade in 1..NUM_BLADES {
+        assert!(geometry.position(blade).height > geometry.position(blade - 1).height);
+    }
+}
+
+#[test]
+fn angle_and_ring_follow_blades_per_turn() {
+    let geometry = ShellGeometry::new(8);
+    assert_eq!(geometry.rings(), 4);
+
+    assert_eq!(geometry.position(0).angle, 0);
+    assert_eq!(geometry.position(2).angle, 64);
+    assert_eq!(geometry.position(4).angle, 128);
+    assert_eq!(geometry.position(8).angle, 0);
+    assert_eq!(geometry.position(7).ring, 0);
+    assert_eq!(geometry.position(8).ring, 1);
+    assert_eq!(geometry.blades_in_ring(1).collect::<Vec<_>>(), (8..16).collect::<Vec<_>>());
+}
+
+#[test]
+fn partial_top_turn_counts_as_a_ring() {
+    let geometry = ShellGeometry::new(10);
+    assert_eq!(geometry.rings(), 4);
+    assert_eq!(geometry.blades_in_ring(3).count(), NUM_BLADES - 30);
+}
+
+#[test]
+fn zero_blades_per_turn_is_not_a_divide_by_zero() {
+    let geometry = ShellGeometry::new(0);
+    assert_eq!(geometry.blades_per_turn(), 1);
+}
+
+#[test]
+fn bands_select_by_height() {
+    let geometry = ShellGeometry::default();
+    let bottom: Vec<usize> = geometry.blades_in_band(0, 20).collect();
+    assert_eq!(bottom, [0, 1, 2]);
+    assert_eq!(geometry.blades_in_band(128, 255).count(), NUM_BLADES);
+}
+
+#[test]
+fn angle_distance_wraps_around() {
+    assert_eq!(angle_distance(10, 20), 10);
+    assert_eq!(angle_distance(250, 5), 11);
+    assert_eq!(angle_distance(0, 128), 128);
+}
@@ -0,0 +1,90 @@
+//! Golden-frame regression tests
+//!
+//! Every registered effect is run for `FRAMES` updates with a fixed seed
+//! and the blade colors are compared against `tests/golden/<effect>.txt`.
+//! After an intentional change to an effect, regenerate the files with
+//! `cargo update-golden` and review the diff.
+
+mod common;
+
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use common::FakeClock;
+use juicy_core::effects::{EffectInfo, EFFECT_REGISTRY};
+use juicy_core::frame::{FrameBuffer, NUM_BLADES};
+use juicy_core::rng::DEFAULT_SEED;
+
+const FRAMES: usize = 200;
+
+fn golden_path(info: &EffectInfo) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.txt", info.name))
+}
+
+/// Runs an effect and renders one line per frame, each blade as `rrggbb`
+fn render(info: &EffectInfo) -> String {
+    let clock = FakeClock::new();
+    let params = (info.default_params)();
+    let mut effect = (info.build)(&params, DEFAULT_SEED);
+    let mut frame = FrameBuffer::new();
+    let mut out = String::new();
+
+    for _ in 0..FRAMES {
+        clock.advance(params.delay_ms);
+        effect.update(&mut frame, &clock);
+
+        let line: Vec<String> = (0..NUM_BLADES)
+            .map(|blade| {
+                let color = frame.get_blade(blade);
+                format!("{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+            })
+            .collect();
+        writeln!(out, "{}", line.join(" ")).unwrap();
+    }
+
+    out
+}
+
+#[test]
+fn effects_match_golden_frames() {
+    let mut failures = String::new();
+
+    for info in EFFECT_REGISTRY {
+        let path = golden_path(info);
+        let expected = match fs::read_to_string(&path) {
+            Ok(expected) => expected,
+            Err(_) => {
+                writeln!(failures, "{}: missing {}", info.name, path.display()).unwrap();
+                continue;
+            }
+        };
+
+        let actual = render(info);
+        let mismatch = actual
+            .lines()
+            .zip(expected.lines())
+            .position(|(a, e)| a != e)
+            .or((actual.lines().count() != expected.lines().count()).then_some(FRAMES));
+
+        if let Some(frame) = mismatch {
+            writeln!(failures, "{}: differs from golden at frame {}", info.name, frame).unwrap();
+        }
+    }
+
+    assert!(failures.is_empty(), "{failures}run `cargo update-golden` if the change is intended");
+}
+
+/// Rewrites the golden files, run through `cargo update-golden`
+#[test]
+#[ignore]
+fn regenerate_golden_frames() {
+    for info in EFFECT_REGISTRY {
+        let path = golden_path(info);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, render(info)).unwrap();
+    }
+}
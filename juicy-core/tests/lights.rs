@@ -0,0 +1,93 @@
+mod common;
+
+use common::{FakeClock, MockStrip};
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::lights::{BladeLights, BLINK_MSEC};
+use juicy_core::sections::{Section, SectionMap};
+use smart_leds::RGB8;
+
+#[test]
+fn refresh_writes_only_when_updated() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+
+    // The first refresh always writes
+    lights.refresh(false, &clock).unwrap();
+    clock.advance(1);
+    lights.refresh(false, &clock).unwrap();
+    assert_eq!(lights.sink().frames.len(), 1);
+
+    lights.set_blade(3, RGB8::new(1, 2, 3), false).unwrap();
+    lights.refresh(true, &clock).unwrap();
+
+    let frames = &lights.sink().frames;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].len(), NUM_BLADES);
+    assert_eq!(frames[1][3], RGB8::new(1, 2, 3));
+}
+
+#[test]
+fn set_blade_rejects_out_of_range() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+
+    assert!(lights.set_blade(NUM_BLADES as u8, RGB8::new(1, 1, 1), false).is_err());
+}
+
+#[test]
+fn blinking_blades_alternate_with_black() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    let red = RGB8::new(10, 0, 0);
+
+    lights.set_blade(0, red, true).unwrap();
+    lights.set_blade(1, red, false).unwrap();
+
+    let mut seen = Vec::new();
+    for _ in 0..4 {
+        clock.advance(BLINK_MSEC + 1);
+        lights.refresh(false, &clock).unwrap();
+        let frame = lights.sink().frames.last().unwrap();
+        assert_eq!(frame[1], red);
+        seen.push(frame[0]);
+    }
+
+    assert!(seen.contains(&red));
+    assert!(seen.contains(&RGB8::default()));
+    assert_ne!(seen[0], seen[1]);
+}
+
+/// Blade 0 drives LEDs 0-2, blade 1 LEDs 3 and 5, every other blade
+/// one LED after those, LED 4 is unused
+static SECTIONS: SectionMap = SectionMap::new(&sections(), NUM_BLADES + 4);
+
+const fn sections() -> [Section; NUM_BLADES] {
+    let mut sections = [const { Section::Range { start: 0, len: 0 } }; NUM_BLADES];
+    sections[0] = Section::Range { start: 0, len: 3 };
+    sections[1] = Section::List(&[3, 5]);
+
+    let mut blade = 2;
+    while blade < NUM_BLADES {
+        sections[blade] = Section::Range { start: blade as u16 + 4, len: 1 };
+        blade += 1;
+    }
+    sections
+}
+
+#[test]
+fn blades_fill_every_led_in_their_section() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::with_sections(MockStrip::new(), &SECTIONS, &clock);
+    let red = RGB8::new(10, 0, 0);
+    let green = RGB8::new(0, 10, 0);
+    let blue = RGB8::new(0, 0, 10);
+
+    lights.set_blade(0, red, false).unwrap();
+    lights.set_blade(1, green, false).unwrap();
+    lights.set_blade(2, blue, false).unwrap();
+    lights.refresh(true, &clock).unwrap();
+
+    let frame = lights.sink().frames.last().unwrap();
+    assert_eq!(frame.len(), NUM_BLADES + 4);
+    assert_eq!(frame[..7], [red, red, red, green, RGB8::default(), green, blue]);
+}
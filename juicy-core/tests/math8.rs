@@ -0,0 +1,54 @@
+use juicy_core::math8::{add_color, blend, fraction8, lerp8, nscale8, qadd8, qsub8, scale8, scale8_video};
+use smart_leds::RGB8;
+
+#[test]
+fn scale8_endpoints() {
+    for value in 0..=255u8 {
+        assert_eq!(scale8(value, 255), value);
+        assert_eq!(scale8(value, 0), 0);
+    }
+    assert_eq!(scale8(200, 128), 100);
+}
+
+#[test]
+fn scale8_video_keeps_dim_pixels_lit() {
+    assert_eq!(scale8(10, 10), 0);
+    assert_eq!(scale8_video(10, 10), 1);
+    assert_eq!(scale8_video(0, 10), 0);
+    assert_eq!(scale8_video(10, 0), 0);
+}
+
+#[test]
+fn saturating_add_and_subtract() {
+    assert_eq!(qadd8(200, 100), 255);
+    assert_eq!(qadd8(20, 10), 30);
+    assert_eq!(qsub8(10, 20), 0);
+    assert_eq!(qsub8(20, 10), 10);
+}
+
+#[test]
+fn lerp8_hits_both_ends_in_either_direction() {
+    assert_eq!(lerp8(10, 200, 0), 10);
+    assert_eq!(lerp8(10, 200, 255), 200);
+    assert_eq!(lerp8(200, 10, 0), 200);
+    assert_eq!(lerp8(200, 10, 255), 10);
+    assert_eq!(lerp8(0, 200, 128), 100);
+}
+
+#[test]
+fn fraction8_maps_onto_full_range() {
+    assert_eq!(fraction8(0, 20), 0);
+    assert_eq!(fraction8(20, 20), 255);
+    assert_eq!(fraction8(30, 20), 255);
+    assert_eq!(fraction8(1, 0), 0);
+}
+
+#[test]
+fn color_helpers_work_per_channel() {
+    let color = RGB8::new(200, 100, 50);
+    assert_eq!(nscale8(color, 255), color);
+    assert_eq!(nscale8(color, 128), RGB8::new(100, 50, 25));
+    assert_eq!(blend(color, RGB8::default(), 255), RGB8::default());
+    assert_eq!(blend(color, RGB8::default(), 0), color);
+    assert_eq!(add_color(color, color), RGB8::new(255, 200, 100));
+}
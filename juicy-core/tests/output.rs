@@ -0,0 +1,144 @@
+mod common;
+
+use common::{FakeClock, MockRgbwStrip, MockStrip};
+use juicy_core::lights::BladeLights;
+use juicy_core::output::{ColorOrder, Gamma, OutputStage, Pixel, WhiteMode, RGBW8};
+use smart_leds::{White, RGB8};
+
+const ORDERS: [ColorOrder; 6] = [
+    ColorOrder::RGB,
+    ColorOrder::RBG,
+    ColorOrder::GRB,
+    ColorOrder::GBR,
+    ColorOrder::BRG,
+    ColorOrder::BGR,
+];
+
+#[test]
+fn gamma_tables_match_float_reference() {
+    for (gamma, exponent) in [
+        (Gamma::Gamma18, 1.8f64),
+        (Gamma::Gamma22, 2.2),
+        (Gamma::Gamma25, 2.5),
+        (Gamma::Gamma28, 2.8),
+    ] {
+        let table = gamma.table();
+        for (i, &value) in table.iter().enumerate() {
+            let expected = ((i as f64 / 255.0).powf(exponent) * 255.0).round() as i32;
+            assert!((value as i32 - expected).abs() <= 1, "{:?}[{}] = {} expected {}", gamma, i, value, expected);
+        }
+        assert_eq!(table[0], 0);
+        assert_eq!(table[255], 255);
+    }
+}
+
+#[test]
+fn linear_gamma_is_identity() {
+    let table = Gamma::Linear.table();
+    for (i, &value) in table.iter().enumerate() {
+        assert_eq!(value as usize, i);
+    }
+}
+
+#[test]
+fn gamma_tables_never_decrease() {
+    let table = Gamma::Gamma28.table();
+    assert!(table.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn default_stage_passes_colors_through() {
+    let stage = OutputStage::new();
+    let color = RGB8::new(12, 130, 250);
+    assert_eq!(stage.apply(color), color);
+}
+
+#[test]
+fn white_balance_scales_each_channel() {
+    let stage = OutputStage::new().with_white_balance(255, 128, 0);
+    assert_eq!(stage.apply(RGB8::new(200, 200, 200)), RGB8::new(200, 100, 0));
+}
+
+#[test]
+fn arrange_and_unarrange_round_trip() {
+    let color = RGB8::new(1, 2, 3);
+    for order in ORDERS {
+        assert_eq!(order.unarrange(order.arrange(color)), color);
+    }
+    assert_eq!(ColorOrder::BRG.arrange(color), [3, 1, 2]);
+}
+
+/// A GRB driver puts the `g` channel first on the wire, so for an RGB
+/// strip red has to arrive in `g`
+#[test]
+fn color_order_remaps_for_driver() {
+    let color = RGB8::new(1, 2, 3);
+    for strip in ORDERS {
+        let stage = OutputStage::new().with_color_order(strip, ColorOrder::GRB);
+        assert_eq!(ColorOrder::GRB.arrange(stage.apply(color)), strip.arrange(color));
+    }
+}
+
+#[test]
+fn lights_apply_output_stage_before_writing() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    lights.set_output(
+        OutputStage::new()
+            .with_gamma(Gamma::Gamma22)
+            .with_color_order(ColorOrder::RGB, ColorOrder::GRB),
+    );
+
+    lights.set_blade(0, RGB8::new(255, 128, 0), false).unwrap();
+    lights.refresh(true, &clock).unwrap();
+
+    let half = Gamma::Gamma22.table()[128];
+    let frame = lights.sink().frames.last().unwrap();
+    assert_eq!(frame[0], RGB8::new(half, 255, 0));
+    assert_eq!(lights.current_leds().next(), Some(RGB8::new(half, 255, 0)));
+}
+
+#[test]
+fn min_of_rgb_moves_common_part_to_white() {
+    let (rgb, white) = WhiteMode::MinOfRgb.extract(RGB8::new(200, 120, 80));
+    assert_eq!(white, 80);
+    assert_eq!(rgb, RGB8::new(120, 40, 0));
+
+    assert_eq!(WhiteMode::Off.extract(RGB8::new(200, 120, 80)), (RGB8::new(200, 120, 80), 0));
+}
+
+/// A warm white LED leaves less red behind than blue for the same color
+#[test]
+fn calibrated_white_accounts_for_tint() {
+    let warm = WhiteMode::Calibrated(RGB8::new(255, 200, 140));
+
+    let (rgb, white) = warm.extract(RGB8::new(255, 200, 140));
+    assert!(white >= 254);
+    assert!(rgb.r <= 1 && rgb.g <= 1 && rgb.b <= 1);
+
+    let (rgb, white) = warm.extract(RGB8::new(100, 100, 100));
+    assert_eq!(white, 100);
+    assert!(rgb.r < rgb.g && rgb.g < rgb.b);
+}
+
+#[test]
+fn rgb_strips_mix_direct_white_into_color() {
+    let stage = OutputStage::new();
+    assert_eq!(RGB8::render(RGB8::new(10, 0, 250), 20, &stage), RGB8::new(30, 20, 255));
+}
+
+#[test]
+fn rgbw_lights_extract_and_add_white() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockRgbwStrip::new(), &clock);
+
+    lights.set_blade(0, RGB8::new(50, 60, 70), false).unwrap();
+    lights.set_blade(1, RGB8::new(50, 60, 70), false).unwrap();
+    lights.set_white(1, 100).unwrap();
+    lights.refresh(true, &clock).unwrap();
+
+    let frame = lights.sink().frames.last().unwrap();
+    assert_eq!(frame[0], RGBW8 { r: 0, g: 10, b: 20, a: White(50) });
+    assert_eq!(frame[1], RGBW8 { r: 0, g: 10, b: 20, a: White(150) });
+    assert_eq!(frame[2], RGBW8::default());
+}
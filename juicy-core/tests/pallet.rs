@@ -0,0 +1,40 @@
+use juicy_core::pallet::{adjust_temperature, get_color_bright, get_temperature};
+use smart_leds::RGB8;
+
+#[test]
+fn temperature_packs_tint_and_level() {
+    assert_eq!(get_temperature(3, 7), 0x37);
+    assert_eq!(get_temperature(6, 15), 0x6f);
+}
+
+#[test]
+fn temperature_clamps_out_of_range_values() {
+    assert_eq!(get_temperature(7, 4), 0x04);
+    assert_eq!(get_temperature(1, 20), 0x1f);
+}
+
+#[test]
+fn adjust_temperature_keeps_tint() {
+    assert_eq!(adjust_temperature(0x25, -1), 0x24);
+    assert_eq!(adjust_temperature(0x25, 3), 0x28);
+}
+
+#[test]
+fn adjust_temperature_goes_dark_below_one() {
+    assert_eq!(adjust_temperature(0x21, -1), 0);
+    assert_eq!(adjust_temperature(0x23, -5), 0);
+}
+
+#[test]
+fn color_bright_scales_by_level() {
+    assert_eq!(get_color_bright(0x1f, 100), RGB8::new(100, 0, 0));
+    assert_eq!(get_color_bright(0x20, 100), RGB8::new(0, 0, 0));
+    assert_eq!(get_color_bright(0x00, 100), RGB8::new(0, 0, 0));
+}
+
+#[test]
+fn color_bright_halves_mixed_tints() {
+    assert_eq!(get_color_bright(0x4f, 100), RGB8::new(50, 0, 50));
+    assert_eq!(get_color_bright(0x5f, 100), RGB8::new(50, 50, 0));
+    assert_eq!(get_color_bright(0x6f, 100), RGB8::new(0, 50, 50));
+}
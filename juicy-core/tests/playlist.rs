@@ -0,0 +1,88 @@
+use juicy_core::effects::EFFECT_REGISTRY;
+use juicy_core::playlist::{Playlist, PlaylistEntry, PlaylistOrder};
+use juicy_core::rng::Rng;
+
+fn playlist_of(order: PlaylistOrder, count: usize) -> Playlist {
+    let mut playlist = Playlist::new(order);
+    for i in 0..count {
+        let entry = PlaylistEntry::new(i % EFFECT_REGISTRY.len(), 100, 50, 10 + i as u32);
+        playlist.push(entry).unwrap();
+    }
+    playlist
+}
+
+#[test]
+fn empty_playlist_has_no_next() {
+    let mut playlist = Playlist::new(PlaylistOrder::Sequential);
+    assert!(playlist.next(&mut Rng::default()).is_none());
+}
+
+#[test]
+fn push_rejects_unknown_effect() {
+    let mut playlist = Playlist::new(PlaylistOrder::Sequential);
+    let entry = PlaylistEntry::new(EFFECT_REGISTRY.len(), 100, 50, 10);
+    assert!(playlist.push(entry).is_err());
+}
+
+#[test]
+fn push_rejects_zero_duration() {
+    let mut playlist = Playlist::new(PlaylistOrder::Sequential);
+    assert!(playlist.push(PlaylistEntry::new(0, 100, 50, 0)).is_err());
+    assert!(playlist.entries().is_empty());
+}
+
+#[test]
+fn sequential_wraps_around() {
+    let mut playlist = playlist_of(PlaylistOrder::Sequential, 3);
+    let mut rng = Rng::default();
+    let durations: Vec<u32> = (0..6).map(|_| playlist.next(&mut rng).unwrap().duration_sec).collect();
+    assert_eq!(durations, [10, 11, 12, 10, 11, 12]);
+}
+
+#[test]
+fn shuffle_plays_every_entry_each_pass_without_repeats() {
+    let mut playlist = playlist_of(PlaylistOrder::Shuffle, 5);
+    let mut rng = Rng::default();
+    let mut last = None;
+
+    for _ in 0..20 {
+        let mut seen = [false; 5];
+        for _ in 0..5 {
+            let duration = playlist.next(&mut rng).unwrap().duration_sec;
+            assert_ne!(Some(duration), last);
+            seen[(duration - 10) as usize] = true;
+            last = Some(duration);
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+}
+
+#[test]
+fn weighted_random_never_repeats_and_respects_zero_weight() {
+    let mut playlist = Playlist::new(PlaylistOrder::WeightedRandom);
+    for (i, weight) in [5u8, 1, 0].iter().enumerate() {
+        let mut entry = PlaylistEntry::new(0, 100, 50, 1 + i as u32);
+        entry.weight = *weight;
+        playlist.push(entry).unwrap();
+    }
+
+    let mut rng = Rng::default();
+    let mut last = None;
+    let mut counts = [0; 3];
+    for _ in 0..200 {
+        let duration = playlist.next(&mut rng).unwrap().duration_sec;
+        assert_ne!(Some(duration), last);
+        counts[duration as usize - 1] += 1;
+        last = Some(duration);
+    }
+
+    assert_eq!(counts[2], 0);
+}
+
+#[test]
+fn default_rotation_covers_the_registry() {
+    let mut playlist = Playlist::default_rotation();
+    assert_eq!(playlist.entries().len(), EFFECT_REGISTRY.len());
+    let first = playlist.next(&mut Rng::default()).unwrap();
+    assert_eq!(EFFECT_REGISTRY[first.effect_id].name, "spark_fire");
+}
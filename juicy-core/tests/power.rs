@@ -0,0 +1,63 @@
+mod common;
+
+use common::{FakeClock, MockStrip};
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::lights::BladeLights;
+use juicy_core::power::PowerBudget;
+use smart_leds::RGB8;
+
+const BUDGET: PowerBudget = PowerBudget::new(20, 1, 500);
+
+#[test]
+fn estimate_adds_idle_and_channel_current() {
+    let leds = [RGB8::new(255, 255, 255), RGB8::new(255, 0, 0), RGB8::default()];
+    assert_eq!(BUDGET.estimate(leds), 3 + 60 + 20);
+}
+
+#[test]
+fn frames_within_budget_are_not_scaled() {
+    assert_eq!(BUDGET.limit_scale(500, 32), 255);
+    assert_eq!(BUDGET.limit_scale(10, 32), 255);
+}
+
+#[test]
+fn limited_frames_fit_the_supply() {
+    let leds = [RGB8::new(255, 255, 255); NUM_BLADES];
+    let draw = BUDGET.estimate(leds);
+    assert_eq!(draw, 32 + 32 * 60);
+
+    let scale = BUDGET.limit_scale(draw, NUM_BLADES);
+    assert!(scale < 255);
+
+    let scaled = leds.map(|led| juicy_core::math8::nscale8(led, scale));
+    let limited = BUDGET.estimate(scaled);
+    assert!(limited <= BUDGET.supply_ma, "{} mA", limited);
+    assert!(limited > BUDGET.supply_ma - 40, "{} mA", limited);
+}
+
+#[test]
+fn lights_scale_frames_over_budget_and_count_them() {
+    let clock = FakeClock::new();
+    let mut lights = BladeLights::new(MockStrip::new(), &clock);
+    lights.set_power_budget(BUDGET);
+
+    lights.set_blade(0, RGB8::new(200, 100, 0), false).unwrap();
+    lights.refresh(true, &clock).unwrap();
+    assert!(!lights.power().is_limited());
+    assert_eq!(lights.sink().frames[0][0], RGB8::new(200, 100, 0));
+
+    for blade in 0..NUM_BLADES {
+        lights.set_blade(blade as u8, RGB8::new(255, 255, 255), false).unwrap();
+    }
+    lights.refresh(true, &clock).unwrap();
+
+    let power = *lights.power();
+    assert!(power.is_limited());
+    assert_eq!(power.limited_frames, 1);
+    assert_eq!(power.draw_ma, 32 + 32 * 60);
+
+    let frame = lights.sink().frames.last().unwrap();
+    assert!(frame[0].r < 255);
+    assert!(BUDGET.estimate(frame.iter().copied()) <= BUDGET.supply_ma);
+    assert_eq!(lights.current_leds().next(), Some(frame[0]));
+}
@@ -0,0 +1,48 @@
+use juicy_core::rng::Rng;
+
+#[test]
+fn same_seed_same_sequence() {
+    let mut a = Rng::new(7);
+    let mut b = Rng::new(7);
+    for _ in 0..100 {
+        assert_eq!(a.next_u32(), b.next_u32());
+    }
+}
+
+#[test]
+fn zero_seed_still_produces_values() {
+    let mut rng = Rng::new(0);
+    assert!((0..10).any(|_| rng.next_u32() != 0));
+}
+
+#[test]
+fn range_stays_in_bounds_and_covers_it() {
+    let mut rng = Rng::new(1);
+    let mut seen = [false; 6];
+    for _ in 0..1000 {
+        let value = rng.range_between(1, 7);
+        assert!((1..7).contains(&value));
+        seen[value as usize - 1] = true;
+    }
+    assert!(seen.iter().all(|s| *s));
+    assert_eq!(rng.range(0), 0);
+}
+
+#[test]
+fn one_in_matches_its_odds() {
+    let mut rng = Rng::new(3);
+    assert!(rng.one_in(1));
+
+    let hits = (0..10_000).filter(|_| rng.one_in(10)).count();
+    assert!((800..1200).contains(&hits), "{hits}");
+}
+
+#[test]
+fn choose_picks_from_the_slice() {
+    let mut rng = Rng::new(5);
+    let items = [3, 5, 7];
+    for _ in 0..50 {
+        assert!(items.contains(rng.choose(&items).unwrap()));
+    }
+    assert!(rng.choose::<u8>(&[]).is_none());
+}
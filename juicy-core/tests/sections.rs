@@ -0,0 +1,43 @@
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::sections::{Section, SectionMap, ONE_PER_BLADE};
+
+fn ranges(len: u16) -> Vec<Section> {
+    (0..NUM_BLADES as u16).map(|blade| Section::Range { start: blade * len, len }).collect()
+}
+
+#[test]
+fn one_per_blade_is_the_identity() {
+    assert_eq!(ONE_PER_BLADE.led_count(), NUM_BLADES);
+    for led in 0..NUM_BLADES {
+        assert_eq!(ONE_PER_BLADE.blade_for_led(led), Some(led));
+    }
+    assert_eq!(ONE_PER_BLADE.blade_for_led(NUM_BLADES), None);
+}
+
+#[test]
+fn ranges_map_runs_of_leds() {
+    let map = SectionMap::new(&ranges(3), NUM_BLADES * 3);
+    assert_eq!(map.blade_for_led(0), Some(0));
+    assert_eq!(map.blade_for_led(5), Some(1));
+    assert_eq!(map.leds_for_blade(2).collect::<Vec<_>>(), [6, 7, 8]);
+}
+
+#[test]
+#[should_panic(expected = "more than one section")]
+fn overlapping_sections_are_rejected() {
+    let mut sections = ranges(1);
+    sections[1] = Section::List(&[0]);
+    SectionMap::new(&sections, NUM_BLADES);
+}
+
+#[test]
+#[should_panic(expected = "past the end of the strip")]
+fn leds_past_the_strip_are_rejected() {
+    SectionMap::new(&ranges(2), NUM_BLADES);
+}
+
+#[test]
+#[should_panic(expected = "one section per blade")]
+fn every_blade_needs_a_section() {
+    SectionMap::new(&ranges(1)[1..], NUM_BLADES);
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use common::FakeClock;
+use juicy_core::clock::Clock;
+use juicy_core::crc::crc32;
+use juicy_core::effects::{find_effect, EffectManager};
+use juicy_core::playlist::{PlaylistEntry, PlaylistOrder};
+use juicy_core::settings::{Settings, SettingsFlash, SettingsStore, SettingsWatch, RECORD_SIZE};
+
+/// Flash that only clears bits, like the real thing
+struct MemFlash {
+    bytes: Vec<u8>,
+    erases: u32,
+}
+
+impl MemFlash {
+    fn new(slots: usize) -> Self {
+        Self { bytes: vec![0xff; slots * RECORD_SIZE], erases: 0 }
+    }
+}
+
+impl SettingsFlash for MemFlash {
+    fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) {
+        buffer.copy_from_slice(&self.bytes[offset..offset + buffer.len()]);
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), &'static str> {
+        for (byte, &new) in self.bytes[offset..offset + data.len()].iter_mut().zip(data) {
+            if *byte != 0xff {
+                return Err("programming a byte that isn't erased");
+            }
+            *byte = new;
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self) -> Result<(), &'static str> {
+        self.bytes.fill(0xff);
+        self.erases += 1;
+        Ok(())
+    }
+}
+
+fn sample(brightness: u8) -> Settings {
+    let mut fire = PlaylistEntry::new(find_effect("fire").unwrap(), 100, 20, 30);
+    fire.param = Some(7);
+    fire.weight = 3;
+    let spiral = PlaylistEntry::new(find_effect("spiral").unwrap(), 200, 40, 90);
+
+    let mut selected = PlaylistEntry::new(find_effect("spiral").unwrap(), 150, 25, 45);
+    selected.param = Some(3);
+
+    Settings {
+        selected: Some(selected),
+        brightness,
+        order: PlaylistOrder::Shuffle,
+        playlist: [fire, spiral].into_iter().collect(),
+    }
+}
+
+#[test]
+fn crc_matches_the_standard() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn records_round_trip() {
+    let settings = sample(64);
+    assert_eq!(Settings::decode(&settings.encode(9)), Ok((settings.clone(), 9)));
+
+    let playlist_only = Settings { selected: None, ..settings };
+    assert_eq!(Settings::decode(&playlist_only.encode(10)), Ok((playlist_only, 10)));
+}
+
+#[test]
+fn bad_records_are_rejected() {
+    let record = sample(64).encode(1);
+
+    let mut corrupt = record;
+    corrupt[7] ^= 1;
+    assert_eq!(Settings::decode(&corrupt), Err("settings record is corrupt"));
+
+    let mut erased = record;
+    erased[..4].fill(0xff);
+    assert_eq!(Settings::decode(&erased), Err("no settings record"));
+
+    let mut newer = record;
+    newer[4] += 1;
+    let crc = crc32(&newer[..RECORD_SIZE - 4]);
+    newer[RECORD_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+    assert_eq!(Settings::decode(&newer), Err("unsupported settings version"));
+}
+
+#[test]
+fn empty_flash_has_no_settings() {
+    let mut store = SettingsStore::new(MemFlash::new(4));
+    assert_eq!(store.load(), None);
+}
+
+#[test]
+fn newest_record_wins_after_a_reboot() {
+    let mut store = SettingsStore::new(MemFlash::new(4));
+    store.load();
+    store.save(&sample(1)).unwrap();
+    store.save(&sample(2)).unwrap();
+
+    let flash = store.flash();
+    let mut rebooted = SettingsStore::new(MemFlash { bytes: flash.bytes.clone(), erases: 0 });
+    assert_eq!(rebooted.load(), Some(sample(2)));
+
+    // Carries on after the records already written
+    rebooted.save(&sample(3)).unwrap();
+    assert_eq!(rebooted.load(), Some(sample(3)));
+    assert_eq!(rebooted.flash().erases, 0);
+}
+
+#[test]
+fn saves_spread_over_the_slots_before_erasing() {
+    let mut store = SettingsStore::new(MemFlash::new(4));
+    store.load();
+
+    for brightness in 0..4 {
+        store.save(&sample(brightness)).unwrap();
+        assert_eq!(store.flash().erases, 0);
+    }
+
+    store.save(&sample(4)).unwrap();
+    assert_eq!(store.flash().erases, 1);
+    assert_eq!(store.load(), Some(sample(4)));
+}
+
+#[test]
+fn torn_record_falls_back_to_the_previous_one() {
+    let mut store = SettingsStore::new(MemFlash::new(4));
+    store.load();
+    store.save(&sample(1)).unwrap();
+
+    // Power lost half way through the next write
+    let mut flash = MemFlash { bytes: store.flash().bytes.clone(), erases: 0 };
+    let torn = sample(2).encode(2);
+    flash.write(RECORD_SIZE, &torn[..RECORD_SIZE / 2]).unwrap();
+
+    let mut rebooted = SettingsStore::new(flash);
+    assert_eq!(rebooted.load(), Some(sample(1)));
+
+    // The torn slot is skipped rather than written over
+    rebooted.save(&sample(3)).unwrap();
+    assert_eq!(rebooted.load(), Some(sample(3)));
+}
+
+#[test]
+fn settings_are_captured_and_restored() {
+    let clock = FakeClock::new();
+    let settings = sample(80);
+
+    let mut manager = EffectManager::new(&clock);
+    settings.apply(&mut manager, &clock).unwrap();
+
+    assert_eq!(manager.effect_name(), "spiral");
+    assert_eq!(manager.brightness(), 80);
+    assert_eq!(manager.effect_duration(), 45);
+    assert_eq!(manager.selected(), settings.selected.as_ref());
+    assert_eq!(Settings::capture(&manager), settings);
+}
+
+#[test]
+fn without_a_selection_the_playlist_starts_over() {
+    let clock = FakeClock::new();
+    let settings = Settings { selected: None, order: PlaylistOrder::Sequential, ..sample(80) };
+
+    let mut manager = EffectManager::new(&clock);
+    settings.apply(&mut manager, &clock).unwrap();
+
+    assert_eq!(manager.effect_name(), "fire");
+    assert_eq!(manager.effect_duration(), 30);
+    assert_eq!(Settings::capture(&manager), settings);
+}
+
+#[test]
+fn playlist_rotation_is_not_a_setting() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+    let settings = Settings::capture(&manager);
+
+    manager.next_effect(&clock);
+    manager.next_effect(&clock);
+
+    assert_eq!(Settings::capture(&manager), settings);
+}
+
+#[test]
+fn picking_an_effect_is_a_setting_until_the_playlist_moves_on() {
+    let clock = FakeClock::new();
+    let mut manager = EffectManager::new(&clock);
+
+    manager.select_effect(find_effect("spiral").unwrap(), &clock).unwrap();
+    manager.set_effect_duration(20);
+    let selected = Settings::capture(&manager).selected.unwrap();
+    assert_eq!(selected.effect_id, find_effect("spiral").unwrap());
+    assert_eq!(selected.duration_sec, 20);
+
+    manager.next_effect(&clock);
+    assert_eq!(Settings::capture(&manager).selected, None);
+}
+
+#[test]
+fn changes_are_saved_once_they_settle() {
+    let clock = FakeClock::new();
+    let mut watch = SettingsWatch::new(1000, Some(sample(1)));
+
+    assert_eq!(watch.poll(sample(1), clock.now()), None);
+
+    assert_eq!(watch.poll(sample(2), clock.now()), None);
+    clock.advance(500);
+    assert_eq!(watch.poll(sample(3), clock.now()), None);
+    clock.advance(999);
+    assert_eq!(watch.poll(sample(3), clock.now()), None);
+    clock.advance(1);
+    assert_eq!(watch.poll(sample(3), clock.now()), Some(sample(3)));
+
+    clock.advance(5000);
+    assert_eq!(watch.poll(sample(3), clock.now()), None);
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use common::FakeClock;
+use juicy_core::clock::Clock;
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::stream::{adalight_header, tpm2_header, Decoded, StreamDecoder, StreamState, StreamTimeout, BYTE_TIMEOUT_MS, TPM2_END};
+use smart_leds::RGB8;
+
+fn adalight(colors: &[RGB8]) -> Vec<u8> {
+    let mut bytes = adalight_header(colors.len() as u16).to_vec();
+    bytes.extend(colors.iter().flat_map(|c| [c.r, c.g, c.b]));
+    bytes
+}
+
+fn tpm2(colors: &[RGB8]) -> Vec<u8> {
+    let mut bytes = tpm2_header(colors.len() as u16 * 3).to_vec();
+    bytes.extend(colors.iter().flat_map(|c| [c.r, c.g, c.b]));
+    bytes.push(TPM2_END);
+    bytes
+}
+
+fn feed_at(decoder: &mut StreamDecoder, bytes: &[u8], clock: &FakeClock) -> Vec<Decoded> {
+    bytes.iter().map(|&byte| decoder.push(byte, clock.now())).collect()
+}
+
+fn feed(decoder: &mut StreamDecoder, bytes: &[u8]) -> Vec<Decoded> {
+    feed_at(decoder, bytes, &FakeClock::new())
+}
+
+fn text(results: &[Decoded]) -> Vec<u8> {
+    results
+        .iter()
+        .flat_map(|d| match d {
+            Decoded::Text(text) => text.as_bytes().to_vec(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+fn frames(results: &[Decoded]) -> usize {
+    results.iter().filter(|&&d| d == Decoded::Frame).count()
+}
+
+#[test]
+fn headers_match_the_formats() {
+    assert_eq!(adalight_header(256), [b'A', b'd', b'a', 0x00, 0xff, 0xaa]);
+    assert_eq!(tpm2_header(0x0102), [0xc9, 0xda, 0x01, 0x02]);
+}
+
+#[test]
+fn decodes_an_adalight_frame() {
+    let colors = [RGB8::new(1, 2, 3), RGB8::new(4, 5, 6)];
+    let mut decoder = StreamDecoder::new();
+
+    let results = feed(&mut decoder, &adalight(&colors));
+
+    assert_eq!(results.last(), Some(&Decoded::Frame));
+    assert_eq!(frames(&results), 1);
+    assert_eq!(&decoder.frame()[..2], &colors);
+    assert!(decoder.frame()[2..].iter().all(|&c| c == RGB8::default()));
+}
+
+#[test]
+fn decodes_a_tpm2_frame() {
+    let colors = [RGB8::new(9, 8, 7), RGB8::new(6, 5, 4), RGB8::new(3, 2, 1)];
+    let mut decoder = StreamDecoder::new();
+
+    let results = feed(&mut decoder, &tpm2(&colors));
+
+    assert_eq!(results.last(), Some(&Decoded::Frame));
+    assert_eq!(frames(&results), 1);
+    assert_eq!(&decoder.frame()[..3], &colors);
+}
+
+#[test]
+fn extra_leds_are_ignored() {
+    let colors: Vec<RGB8> = (0..NUM_BLADES as u8 + 10).map(|i| RGB8::new(i, i, i)).collect();
+    let mut decoder = StreamDecoder::new();
+
+    assert_eq!(frames(&feed(&mut decoder, &adalight(&colors))), 1);
+    assert_eq!(&decoder.frame()[..], &colors[..NUM_BLADES]);
+
+    assert_eq!(frames(&feed(&mut decoder, &tpm2(&colors))), 1);
+    assert_eq!(&decoder.frame()[..], &colors[..NUM_BLADES]);
+}
+
+#[test]
+fn console_text_passes_through() {
+    let mut decoder = StreamDecoder::new();
+
+    let results = feed(&mut decoder, b"status\n");
+
+    assert_eq!(text(&results), b"status\n");
+}
+
+#[test]
+fn a_broken_header_is_console_text() {
+    let mut decoder = StreamDecoder::new();
+
+    assert_eq!(text(&feed(&mut decoder, b"select Adder\n")), b"select Adder\n");
+    assert_eq!(text(&feed(&mut decoder, b"AAd\n")), b"AAd\n");
+}
+
+#[test]
+fn a_frame_can_follow_a_broken_header() {
+    let colors = [RGB8::new(1, 2, 3)];
+    let mut decoder = StreamDecoder::new();
+
+    let mut bytes = b"Ad".to_vec();
+    bytes.extend(adalight(&colors));
+    bytes.push(b'A');
+    bytes.extend(tpm2(&colors));
+    let results = feed(&mut decoder, &bytes);
+
+    assert_eq!(frames(&results), 2);
+    assert_eq!(text(&results), b"AdA");
+    assert_eq!(decoder.frame()[0], colors[0]);
+}
+
+#[test]
+fn a_stalled_frame_is_dropped() {
+    let clock = FakeClock::new();
+    let colors = [RGB8::new(1, 2, 3), RGB8::new(4, 5, 6)];
+    let bytes = adalight(&colors);
+    let mut decoder = StreamDecoder::new();
+
+    // A short pause within a frame is fine
+    feed_at(&mut decoder, &bytes[..7], &clock);
+    clock.advance(BYTE_TIMEOUT_MS - 1);
+    assert_eq!(frames(&feed_at(&mut decoder, &bytes[7..], &clock)), 1);
+
+    // A long one drops the frame, the next one starts cleanly
+    feed_at(&mut decoder, &bytes[..7], &clock);
+    clock.advance(BYTE_TIMEOUT_MS);
+    let results = feed_at(&mut decoder, b"status\n", &clock);
+    assert_eq!(text(&results), b"status\n");
+    assert_eq!(frames(&feed_at(&mut decoder, &bytes, &clock)), 1);
+}
+
+#[test]
+fn typed_text_is_not_held_as_a_header() {
+    let clock = FakeClock::new();
+    let mut decoder = StreamDecoder::new();
+
+    assert_eq!(text(&feed_at(&mut decoder, b"A", &clock)), b"");
+    clock.advance(1000);
+    assert_eq!(text(&feed_at(&mut decoder, b"d", &clock)), b"Ad");
+}
+
+#[test]
+fn bad_checksum_drops_the_frame() {
+    let mut bytes = adalight(&[RGB8::new(1, 2, 3)]);
+    bytes[5] ^= 1;
+    let mut decoder = StreamDecoder::new();
+
+    let results = feed(&mut decoder, &bytes);
+
+    assert_eq!(frames(&results), 0);
+    assert_eq!(decoder.frame()[0], RGB8::default());
+}
+
+#[test]
+fn bad_tpm2_end_byte_drops_the_frame() {
+    let mut bytes = tpm2(&[RGB8::new(1, 2, 3)]);
+    *bytes.last_mut().unwrap() = 0;
+    let mut decoder = StreamDecoder::new();
+
+    assert_eq!(frames(&feed(&mut decoder, &bytes)), 0);
+    assert_eq!(decoder.frame()[0], RGB8::default());
+}
+
+#[test]
+fn tpm2_commands_are_skipped() {
+    let mut decoder = StreamDecoder::new();
+
+    let results = feed(&mut decoder, &[0xc9, 0xc0, 0x00, 0x02, 0xaa, 0xbb, 0x36]);
+
+    assert!(results.iter().all(|&d| d == Decoded::Pending));
+    assert_eq!(frames(&feed(&mut decoder, &tpm2(&[RGB8::new(5, 5, 5)]))), 1);
+}
+
+#[test]
+fn back_to_back_frames() {
+    let mut bytes = adalight(&[RGB8::new(1, 1, 1)]);
+    bytes.extend(tpm2(&[RGB8::new(2, 2, 2)]));
+    bytes.extend(adalight(&[RGB8::new(3, 3, 3)]));
+    let mut decoder = StreamDecoder::new();
+
+    assert_eq!(frames(&feed(&mut decoder, &bytes)), 3);
+    assert_eq!(decoder.frame()[0], RGB8::new(3, 3, 3));
+}
+
+#[test]
+fn stream_times_out() {
+    let clock = FakeClock::new();
+    let mut timeout = StreamTimeout::new(1000);
+    assert_eq!(timeout.poll(clock.now()), StreamState::Idle);
+
+    timeout.frame_received(clock.now());
+    clock.advance(999);
+    assert_eq!(timeout.poll(clock.now()), StreamState::Streaming);
+
+    timeout.frame_received(clock.now());
+    clock.advance(999);
+    assert_eq!(timeout.poll(clock.now()), StreamState::Streaming);
+
+    clock.advance(1);
+    assert_eq!(timeout.poll(clock.now()), StreamState::Stopped);
+    assert_eq!(timeout.poll(clock.now()), StreamState::Idle);
+}
@@ -0,0 +1,47 @@
+use juicy_core::output::RGBW8;
+use juicy_core::ws2812::{buffer_len, buffer_len_for, encode, BYTES_PER_LED, BYTES_PER_RGBW_LED, RESET_BYTES};
+use smart_leds::{White, RGB8};
+
+#[test]
+fn encodes_green_red_blue_msb_first() {
+    let mut buffer = [0xffu8; buffer_len(1)];
+    let count = encode([RGB8::new(0x00, 0xff, 0x81)], &mut buffer).unwrap();
+    assert_eq!(count, 1);
+
+    // Green 0xff
+    assert_eq!(buffer[0..4], [0b1110_1110; 4]);
+    // Red 0x00
+    assert_eq!(buffer[4..8], [0b1000_1000; 4]);
+    // Blue 0x81 = 10 00 00 01
+    assert_eq!(buffer[8..12], [0b1110_1000, 0b1000_1000, 0b1000_1000, 0b1000_1110]);
+    // Reset gap
+    assert!(buffer[BYTES_PER_LED..].iter().all(|b| *b == 0));
+    assert_eq!(buffer.len(), BYTES_PER_LED + RESET_BYTES);
+}
+
+#[test]
+fn short_frames_clear_the_rest_of_the_buffer() {
+    let mut buffer = [0xffu8; buffer_len(3)];
+    assert_eq!(encode([RGB8::new(1, 2, 3)], &mut buffer), Ok(1));
+    assert!(buffer[BYTES_PER_LED..].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn too_many_leds_is_an_error() {
+    let mut buffer = [0u8; buffer_len(1)];
+    let leds = [RGB8::default(); 2];
+    assert!(encode(leds, &mut buffer).is_err());
+}
+
+#[test]
+fn rgbw_leds_send_white_last() {
+    let mut buffer = [0xffu8; buffer_len_for::<RGBW8>(1)];
+    let led = RGBW8 { r: 0, g: 0xff, b: 0, a: White(0x81) };
+    assert_eq!(encode([led], &mut buffer), Ok(1));
+
+    assert_eq!(buffer[0..4], [0b1110_1110; 4]);
+    assert_eq!(buffer[4..12], [0b1000_1000; 8]);
+    assert_eq!(buffer[12..16], [0b1110_1000, 0b1000_1000, 0b1000_1000, 0b1000_1110]);
+    assert!(buffer[BYTES_PER_RGBW_LED..].iter().all(|b| *b == 0));
+    assert_eq!(buffer.len(), BYTES_PER_RGBW_LED + RESET_BYTES);
+}
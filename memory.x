@@ -1,7 +1,9 @@
 MEMORY
 {
   /* NOTE K = KiBi = 1024 bytes */
-  FLASH (rx) : ORIGIN = 0x08000000, LENGTH = 1024K
+  /* The last 128K sector (11) is left out for the saved settings */
+  FLASH (rx) : ORIGIN = 0x08000000, LENGTH = 896K
+  SETTINGS (r) : ORIGIN = 0x080E0000, LENGTH = 128K
   CCMRAM (rwx) : ORIGIN = 0x10000000, LENGTH = 64K
   RAM (rwx) : ORIGIN = 0x20000000, LENGTH = 128K
 }
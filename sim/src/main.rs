@@ -0,0 +1,229 @@
+//! Terminal simulator for the shell effects
+//!
+//! Runs `EffectManager` against a simulated clock and draws the blades as
+//! a spiral of truecolor cells, so effects can be developed without
+//! flashing the board.
+
+use std::f32::consts::TAU;
+use std::io::{stdout, Stdout, Write};
+use std::time::{Duration, Instant as StdInstant};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use smart_leds::RGB8;
+
+use juicy_core::blink::Blink;
+use juicy_core::clock::Clock;
+use juicy_core::effects::{EffectManager, EFFECT_REGISTRY};
+use juicy_core::frame::{BladeSink, NUM_BLADES};
+use juicy_core::geometry::ShellGeometry;
+use juicy_core::math8::{add_color, nscale8};
+
+/// Real time between simulation steps
+const STEP_MSEC: u64 = 5;
+const SPEEDS: [u32; 7] = [0, 1, 2, 4, 8, 16, 32];
+
+/// Clock advanced by the simulator rather than by hardware
+struct SimClock {
+    now_ms: u32,
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> fugit::Instant<u32, 1, 1000> {
+        fugit::Instant::<u32, 1, 1000>::from_ticks(self.now_ms)
+    }
+}
+
+/// Stands in for `LightPorts`, remembering what each blade was set to
+struct MockLeds {
+    colors: [RGB8; NUM_BLADES],
+    whites: [u8; NUM_BLADES],
+    blink: [Option<Blink>; NUM_BLADES],
+}
+
+impl BladeSink for MockLeds {
+    fn set_blade(&mut self, blade: u8, color: RGB8, blink: bool) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.colors[blade] = color;
+        self.blink[blade] = if blink { Some(Blink::DEFAULT) } else { None };
+
+        Ok(())
+    }
+
+    fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.whites[blade] = white;
+
+        Ok(())
+    }
+
+    fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        let blade = blade as usize;
+        if blade >= NUM_BLADES {
+            return Err("blade index out of range")
+        }
+
+        self.blink[blade] = blink;
+
+        Ok(())
+    }
+}
+
+struct Screen {
+    out: Stdout,
+    positions: [(u16, u16); NUM_BLADES],
+}
+
+impl Screen {
+    fn new() -> std::io::Result<Self> {
+        let mut out = stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+
+        Ok(Self { out, positions: spiral_positions() })
+    }
+
+    fn draw(&mut self, leds: &MockLeds, clock: &SimClock, manager: &EffectManager, speed: u32) -> std::io::Result<()> {
+        for (blade, &(x, y)) in self.positions.iter().enumerate() {
+            // White shows as it would on an RGB strip
+            let white = leds.whites[blade];
+            let color = add_color(leds.colors[blade], RGB8::new(white, white, white));
+            let level = leds.blink[blade].map_or(255, |blink| blink.level(clock.now_ms));
+            let color = nscale8(color, level);
+
+            queue!(
+                self.out,
+                MoveTo(x, y),
+                SetForegroundColor(Color::Rgb { r: color.r, g: color.g, b: color.b }),
+                Print("██"),
+            )?;
+        }
+
+        let status_row = self.positions.iter().map(|&(_, y)| y).max().unwrap_or(0) + 2;
+        queue!(
+            self.out,
+            ResetColor,
+            MoveTo(0, status_row),
+            Clear(ClearType::CurrentLine),
+            Print(format!(
+                "effect: {} ({}/{})   speed: {}x   time: {}.{:03}s",
+                manager.effect_name(),
+                manager.effect_index() + 1,
+                EFFECT_REGISTRY.len(),
+                speed,
+                clock.now_ms / 1000,
+                clock.now_ms % 1000,
+            )),
+            MoveTo(0, status_row + 1),
+            Print("[n] next  [1-9] select  [+/-] speed  [space] pause  [q] quit"),
+        )?;
+
+        self.out.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Lays the blades out on a spiral, blade 0 at the core
+fn spiral_positions() -> [(u16, u16); NUM_BLADES] {
+    let geometry = ShellGeometry::default();
+    let max_radius = 2.0 + geometry.rings() as f32 * 3.0;
+    let mut positions = [(0, 0); NUM_BLADES];
+
+    for (blade, position) in positions.iter_mut().enumerate() {
+        let shell = geometry.position(blade);
+        let turn = shell.angle as f32 / 256.0;
+        let angle = turn * TAU;
+        let radius = 2.0 + (shell.ring as f32 + turn) * 3.0;
+        // Cells are two characters wide to keep the spiral round
+        let x = (max_radius + radius * angle.cos()) * 2.0;
+        let y = max_radius + radius * angle.sin();
+        *position = (x.round() as u16, y.round() as u16);
+    }
+
+    positions
+}
+
+fn main() -> std::io::Result<()> {
+    let mut clock = SimClock { now_ms: 0 };
+    let mut leds = MockLeds {
+        colors: [RGB8::default(); NUM_BLADES],
+        whites: [0; NUM_BLADES],
+        blink: [None; NUM_BLADES],
+    };
+    let mut manager = EffectManager::new(&clock);
+    let mut screen = Screen::new()?;
+
+    let mut speed_index = 1;
+    let mut paused_speed = None;
+    let mut last_step = StdInstant::now();
+
+    loop {
+        if event::poll(Duration::from_millis(STEP_MSEC))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('n') | KeyCode::Right => manager.next_effect(&clock),
+                    KeyCode::Char(c @ '1'..='9') => {
+                        let effect_id = c as usize - '1' as usize;
+                        let _ = manager.select_effect(effect_id, &clock);
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => {
+                        speed_index = (speed_index + 1).min(SPEEDS.len() - 1);
+                        paused_speed = None;
+                    }
+                    KeyCode::Char('-') | KeyCode::Down => {
+                        speed_index = speed_index.saturating_sub(1).max(1);
+                        paused_speed = None;
+                    }
+                    KeyCode::Char(' ') => match paused_speed.take() {
+                        Some(index) => speed_index = index,
+                        None => {
+                            paused_speed = Some(speed_index);
+                            speed_index = 0;
+                        }
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        // Step the simulation a millisecond at a time, like the firmware loop.
+        // Only whole milliseconds are used up, the remainder carries over so
+        // simulated time keeps up with real time.
+        let real_ms = last_step.elapsed().as_millis() as u32;
+        last_step += Duration::from_millis(real_ms as u64);
+
+        let mut updated = false;
+        for _ in 0..real_ms * SPEEDS[speed_index] {
+            clock.now_ms = clock.now_ms.wrapping_add(1);
+            updated |= manager.update(&mut leds, &clock);
+        }
+
+        if updated || real_ms > 0 {
+            screen.draw(&leds, &clock, &manager, SPEEDS[speed_index])?;
+        }
+    }
+
+    Ok(())
+}
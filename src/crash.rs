@@ -0,0 +1,35 @@
+use core::mem::MaybeUninit;
+use core::panic::PanicInfo;
+use core::ptr::addr_of_mut;
+
+use cortex_m::peripheral::SCB;
+
+use juicy_core::crash::CrashRecord;
+
+/// Left alone by the startup code, so it survives the reset after a panic
+#[link_section = ".uninit.CRASH_RECORD"]
+static mut CRASH_RECORD: MaybeUninit<CrashRecord> = MaybeUninit::uninit();
+
+/// Records the panic for the next boot to report, then resets
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    // Nothing else runs with interrupts off, and every bit pattern is a
+    // valid (if invalid looking) record
+    let record = unsafe { &mut *addr_of_mut!(CRASH_RECORD).cast::<CrashRecord>() };
+    match info.location() {
+        Some(location) => record.record(&info.message(), location.file(), location.line(), location.column()),
+        None => record.record(&info.message(), "<unknown>", 0, 0),
+    }
+
+    SCB::sys_reset()
+}
+
+/// The crash that caused the last reset, if any, reported only once
+pub fn take_previous() -> Option<CrashRecord> {
+    let record = unsafe { &mut *addr_of_mut!(CRASH_RECORD).cast::<CrashRecord>() };
+    let previous = record.is_valid().then_some(*record);
+    record.clear();
+    previous
+}
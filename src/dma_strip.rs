@@ -0,0 +1,197 @@
+use crate::hal::dma::config::DmaConfig;
+use crate::hal::dma::traits::{Channel, DMASet, PeriAddress, Stream, StreamISR};
+use crate::hal::dma::{ChannelX, MemoryToPeripheral, Transfer};
+use crate::hal::spi::{self, Tx};
+
+use core::marker::PhantomData;
+
+use juicy_core::bitstream::Bitstream;
+use smart_leds::{SmartLedsWrite, RGB8};
+
+/// Why a strip couldn't take a frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StripError {
+    /// The DMA stream hit a bus error and stopped
+    Transfer,
+    /// The DMA FIFO or direct mode transfer under ran
+    Fifo,
+    /// The frame doesn't fit the strip's buffer
+    Encode(&'static str),
+}
+
+/// Peripheral fed by a strip's DMA, that can be put back in a clean state
+pub trait ResetPeripheral {
+    /// Resets the peripheral and sets its last configuration up again
+    fn reset(&mut self);
+}
+
+impl<SPI: spi::Instance> ResetPeripheral for Tx<SPI> {
+    fn reset(&mut self) {
+        // NOTE(unsafe) the SPI belongs to this strip, whose transfer is stopped
+        let spi = unsafe { &*SPI::ptr() };
+        let cr1 = spi.cr1.read().bits();
+        let cr2 = spi.cr2.read().bits();
+
+        unsafe { SPI::reset_unchecked() };
+
+        spi.cr2.write(|w| unsafe { w.bits(cr2) });
+        spi.cr1.write(|w| unsafe { w.bits(cr1) }.spe().clear_bit());
+        spi.cr1.modify(|_, w| w.spe().set_bit());
+    }
+}
+
+/// LED strip clocked out of SPI by DMA
+///
+/// Frames are rendered into a back buffer while the front buffer is being
+/// transferred, then the two are swapped. Writing never waits for the
+/// SPI: if the previous frame is still going out the new one is kept
+/// pending and started by `poll()`.
+///
+/// `COLOR` picks the encoding: `RGB8` for WS2812 strips, `RGBW8` for
+/// SK6812 RGBW ones or `Apa102Color` for APA102/SK9822.
+pub struct DmaStrip<STREAM, const CHANNEL: u8, PERIPHERAL, const BUF: usize, COLOR = RGB8>
+where
+    STREAM: Stream,
+    PERIPHERAL: PeriAddress<MemSize = u8> + DMASet<STREAM, CHANNEL, MemoryToPeripheral>,
+{
+    /// Only `None` while being restarted
+    transfer: Option<Transfer<STREAM, CHANNEL, PERIPHERAL, MemoryToPeripheral, &'static mut [u8; BUF]>>,
+    back: &'static mut [u8; BUF],
+    pending: bool,
+    /// Latched from the stream's error flags until `restart`
+    fault: Option<StripError>,
+    color: PhantomData<COLOR>,
+}
+
+impl<STREAM, const CHANNEL: u8, PERIPHERAL, const BUF: usize, COLOR> DmaStrip<STREAM, CHANNEL, PERIPHERAL, BUF, COLOR>
+where
+    STREAM: Stream,
+    ChannelX<CHANNEL>: Channel,
+    PERIPHERAL: PeriAddress<MemSize = u8> + DMASet<STREAM, CHANNEL, MemoryToPeripheral> + ResetPeripheral,
+    COLOR: Bitstream,
+{
+    /// Starts clocking out `front` (all LEDs off) and keeps `back` for
+    /// the first frame
+    pub fn new(
+        stream: STREAM,
+        peripheral: PERIPHERAL,
+        front: &'static mut [u8; BUF],
+        back: &'static mut [u8; BUF],
+    ) -> Self {
+        Self {
+            transfer: Some(Self::start(stream, peripheral, front)),
+            back,
+            pending: false,
+            fault: None,
+            color: PhantomData,
+        }
+    }
+
+    fn start(
+        stream: STREAM,
+        peripheral: PERIPHERAL,
+        front: &'static mut [u8; BUF],
+    ) -> Transfer<STREAM, CHANNEL, PERIPHERAL, MemoryToPeripheral, &'static mut [u8; BUF]> {
+        let _ = COLOR::encode(core::iter::empty(), &mut front[..]);
+
+        let config = DmaConfig::default()
+            .memory_increment(true)
+            .transfer_complete_interrupt(false);
+        let mut transfer = Transfer::init_memory_to_peripheral(stream, peripheral, front, None, config);
+        transfer.start(|_| {});
+        transfer
+    }
+
+    /// Stops the stream, resets the SPI and sets the transfer up from
+    /// scratch, blanking the strip. Any pending frame is dropped.
+    pub fn restart(&mut self) {
+        let Some(mut transfer) = self.transfer.take() else {
+            return;
+        };
+
+        // Waits for the stream to actually stop before touching the SPI
+        transfer.pause(|_| {});
+        let (stream, mut peripheral, front, _) = transfer.release();
+        peripheral.reset();
+
+        self.transfer = Some(Self::start(stream, peripheral, front));
+        self.pending = false;
+        self.fault = None;
+    }
+
+    /// True while both buffers are taken, one frame going out and the
+    /// next waiting for it. A faulted strip is never busy, its next write
+    /// reports the fault.
+    pub fn is_busy(&self) -> bool {
+        self.pending && self.check_fault().is_none()
+    }
+
+    /// Error flags raised by the stream since the last restart
+    fn check_fault(&self) -> Option<StripError> {
+        let transfer = self.transfer.as_ref()?;
+
+        if self.fault.is_some() {
+            self.fault
+        } else if transfer.is_transfer_error() {
+            Some(StripError::Transfer)
+        } else if transfer.is_fifo_error() || transfer.is_direct_mode_error() {
+            Some(StripError::Fifo)
+        } else {
+            None
+        }
+    }
+
+    /// Starts the pending frame once the previous transfer has completed
+    pub fn poll(&mut self) {
+        self.fault = self.check_fault();
+        if self.fault.is_some() {
+            return;
+        }
+
+        let Some(transfer) = self.transfer.as_mut() else {
+            return;
+        };
+
+        if !self.pending || !transfer.is_transfer_complete() {
+            return;
+        }
+
+        // Swaps the buffers, the rendered frame goes out and the sent one
+        // becomes the back buffer
+        let back = &mut self.back;
+        // NOTE(unsafe) single buffered, the stream is stopped before the
+        // old buffer is handed back
+        let _ = unsafe { transfer.next_transfer_with(|sent, _| (core::mem::replace(back, sent), ())) };
+        self.pending = false;
+    }
+}
+
+impl<STREAM, const CHANNEL: u8, PERIPHERAL, const BUF: usize, COLOR> SmartLedsWrite for DmaStrip<STREAM, CHANNEL, PERIPHERAL, BUF, COLOR>
+where
+    STREAM: Stream,
+    ChannelX<CHANNEL>: Channel,
+    PERIPHERAL: PeriAddress<MemSize = u8> + DMASet<STREAM, CHANNEL, MemoryToPeripheral> + ResetPeripheral,
+    COLOR: Bitstream,
+{
+    type Error = StripError;
+    type Color = COLOR;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        self.poll();
+        if let Some(fault) = self.fault {
+            return Err(fault);
+        }
+
+        // Rendering overlaps with the transfer of the previous frame
+        COLOR::encode(iterator.map(Into::into), &mut self.back[..]).map_err(StripError::Encode)?;
+
+        self.pending = true;
+        self.poll();
+
+        Ok(())
+    }
+}
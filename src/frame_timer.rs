@@ -0,0 +1,64 @@
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use cortex_m::interrupt::Mutex;
+use cortex_m::peripheral::NVIC;
+
+use crate::hal::pac::{self, interrupt, TIM3};
+use crate::hal::prelude::*;
+use crate::hal::rcc::Clocks;
+use crate::hal::timer::{CounterHz, Event, Flag};
+
+/// Frame ticks since start up, counted by the TIM3 interrupt
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+/// Kept here so the interrupt can clear its flag
+static TIMER: Mutex<RefCell<Option<CounterHz<TIM3>>>> = Mutex::new(RefCell::new(None));
+
+/// Frame scheduler tick from TIM3
+///
+/// The main loop sleeps in `wait()` until the next tick, so the MCU is
+/// idle between frames rather than spinning on the clock.
+pub struct FrameTimer {
+    last_tick: u32,
+}
+
+impl FrameTimer {
+    pub fn new(tim3: TIM3, frame_hz: u32, clocks: &Clocks) -> Self {
+        let mut timer = tim3.counter_hz(clocks);
+        timer.start(frame_hz.Hz()).unwrap();
+        timer.listen(Event::Update);
+
+        cortex_m::interrupt::free(|cs| TIMER.borrow(cs).replace(Some(timer)));
+        unsafe { NVIC::unmask(pac::Interrupt::TIM3) };
+
+        Self { last_tick: TICKS.load(Ordering::Relaxed) }
+    }
+
+    /// Sleeps until the next tick, returns how many ticks went by since
+    /// the last frame, more than one means frames were missed
+    pub fn wait(&mut self) -> u32 {
+        loop {
+            let tick = TICKS.load(Ordering::Relaxed);
+            if tick != self.last_tick {
+                let elapsed = tick.wrapping_sub(self.last_tick);
+                self.last_tick = tick;
+                return elapsed;
+            }
+
+            // Any interrupt wakes us, the tick is checked again
+            cortex_m::asm::wfi();
+        }
+    }
+}
+
+#[interrupt]
+fn TIM3() {
+    cortex_m::interrupt::free(|cs| {
+        if let Some(timer) = TIMER.borrow(cs).borrow_mut().as_mut() {
+            timer.clear_flags(Flag::Update);
+        }
+    });
+
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
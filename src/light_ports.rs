@@ -1,109 +1,206 @@
 
 
-use ws2812_spi as ws2812;
-
 use crate::hal::rcc::*;
 use crate::hal::pac::*;
+use crate::hal::dma::{Stream3, Stream4, Stream5};
 use crate::hal::gpio::{NoPin, Pin};
-use crate::hal::pac::TIM2;
 use crate::hal::prelude::*;
-use crate::hal::timer::Counter;
-use fugit::Instant;
 
 
-use crate::hal::spi::Spi;
-use crate::ws2812::prerendered::Ws2812;
+use crate::hal::spi::{Spi, Tx};
+#[cfg(feature = "apa102")]
+use crate::hal::spi::{Mode, Phase, Polarity};
+
+use cortex_m::singleton;
+use juicy_core::blink::Blink;
+use juicy_core::channels::{Channel, ChannelError, MultiChannel};
+use juicy_core::clock::Clock;
+use juicy_core::frame::BladeSink;
+use juicy_core::lights::BladeLights;
+use juicy_core::power::PowerReport;
+#[cfg(feature = "apa102")]
+use juicy_core::apa102;
+#[cfg(not(feature = "apa102"))]
+use juicy_core::ws2812::buffer_len_for;
 
+use crate::dma_strip::{DmaStrip, StripError};
+use crate::shell_map::{LedColor, DITHERING, OUTPUT, POWER_BUDGET, SHELL_MAP, SPI1_LEDS, SPI2_LEDS, SPI3_LEDS};
 
-use smart_leds::{SmartLedsWrite, RGB8};
+use smart_leds::RGB8;
 // use rtt_target::{rprintln, rtt_init_print};
 
-pub const LED_NUM: usize = 32;
-const BLINK_MSEC: u32 = 200;
+/// WS2812 timing is made by the SPI data rate, at 4 SPI bits per data bit
+#[cfg(not(feature = "apa102"))]
+use ws2812_spi::MODE as SPI_MODE;
+#[cfg(not(feature = "apa102"))]
+const SPI_HZ: u32 = 3_000_000;
+
+/// APA102 take data on the rising edge of their own clock line
+#[cfg(feature = "apa102")]
+const SPI_MODE: Mode = Mode {
+    polarity: Polarity::IdleLow,
+    phase: Phase::CaptureOnFirstTransition,
+};
+#[cfg(feature = "apa102")]
+const SPI_HZ: u32 = 6_000_000;
+
+/// Bitstream size for the strip type fitted
+#[cfg(not(feature = "apa102"))]
+const fn strip_buffer(leds: usize) -> usize {
+    buffer_len_for::<LedColor>(leds)
+}
 
-pub struct LightPorts<'a> {
-    led_data: [RGB8; LED_NUM],
-    blink_mask: [bool; LED_NUM],
-    ws: Ws2812<'a, Spi<SPI1>>,
-    sys_timer: &'a Counter<TIM2, 1000>,
-    blink_on: bool,
-    blink_next: Instant<u32, 1, 1000>,
+#[cfg(feature = "apa102")]
+const fn strip_buffer(leds: usize) -> usize {
+    apa102::buffer_len(leds)
 }
 
-impl <'a> LightPorts<'a> {
-    pub fn new(
-        pa5: Pin<'A', 5>,
-        pa7: Pin<'A', 7>,
-        spi: SPI1,
-        buffer: &'a mut [u8; (LED_NUM * 12) + 30],
-        clocks: &Clocks,
-        sys_timer: &'a Counter<TIM2, 1000>,
-    ) -> Self {
-        // SPI1 with 3Mhz
-        let spi: Spi<SPI1> = Spi::new(
-            spi,
-            (pa5.into_alternate(), NoPin::new(), pa7.into_alternate()),
-            ws2812::MODE,
-            3_000_000.Hz(),
-            clocks,
-        );
-
-        let data = [RGB8::new(0x00, 0x00, 0x00); LED_NUM];
-
-        // Create Ws2812 instance with the mutable reference to the buffer
-        let ws = Ws2812::new(spi, buffer);
-
-        // Return the LightPorts instance
-        Self {
-            led_data: data,
-            blink_mask: [false; LED_NUM],
-            blink_on: false,
-            blink_next: sys_timer.now(),
-            ws,
-            sys_timer
-        }
-    }
+const SPI1_BUFFER: usize = strip_buffer(SPI1_LEDS);
+const SPI2_BUFFER: usize = strip_buffer(SPI2_LEDS);
+const SPI3_BUFFER: usize = strip_buffer(SPI3_LEDS);
+
+/// SPI1 TX is on DMA2 stream 3 channel 3
+pub type Spi1Strip = DmaStrip<Stream3<DMA2>, 3, Tx<SPI1>, SPI1_BUFFER, LedColor>;
+/// SPI2 TX is on DMA1 stream 4 channel 0
+pub type Spi2Strip = DmaStrip<Stream4<DMA1>, 0, Tx<SPI2>, SPI2_BUFFER, LedColor>;
+/// SPI3 TX is on DMA1 stream 5 channel 0
+pub type Spi3Strip = DmaStrip<Stream5<DMA1>, 0, Tx<SPI3>, SPI3_BUFFER, LedColor>;
+
+type Strips = MultiChannel<(Channel<Spi1Strip>, Channel<Spi2Strip>, Channel<Spi3Strip>)>;
+
+/// Strip on SPI1, PA7 data and PA5 clock (APA102 only)
+///
+/// Can only be called once, the DMA buffers are statically allocated.
+pub fn spi1_strip(pa5: Pin<'A', 5>, pa7: Pin<'A', 7>, spi: SPI1, stream: Stream3<DMA2>, clocks: &Clocks) -> Spi1Strip {
+    let spi: Spi<SPI1> = Spi::new(
+        spi,
+        (pa5.into_alternate(), NoPin::new(), pa7.into_alternate()),
+        SPI_MODE,
+        SPI_HZ.Hz(),
+        clocks,
+    );
+
+    let front = singleton!(: [u8; SPI1_BUFFER] = [0; SPI1_BUFFER]).unwrap();
+    let back = singleton!(: [u8; SPI1_BUFFER] = [0; SPI1_BUFFER]).unwrap();
+    DmaStrip::new(stream, spi.use_dma().tx(), front, back)
+}
+
+/// Strip on SPI2, PB15 data and PB13 clock
+pub fn spi2_strip(pb13: Pin<'B', 13>, pb15: Pin<'B', 15>, spi: SPI2, stream: Stream4<DMA1>, clocks: &Clocks) -> Spi2Strip {
+    let spi: Spi<SPI2> = Spi::new(
+        spi,
+        (pb13.into_alternate(), NoPin::new(), pb15.into_alternate()),
+        SPI_MODE,
+        SPI_HZ.Hz(),
+        clocks,
+    );
+
+    let front = singleton!(: [u8; SPI2_BUFFER] = [0; SPI2_BUFFER]).unwrap();
+    let back = singleton!(: [u8; SPI2_BUFFER] = [0; SPI2_BUFFER]).unwrap();
+    DmaStrip::new(stream, spi.use_dma().tx(), front, back)
+}
+
+/// Strip on SPI3, PC12 data and PC10 clock
+pub fn spi3_strip(pc10: Pin<'C', 10>, pc12: Pin<'C', 12>, spi: SPI3, stream: Stream5<DMA1>, clocks: &Clocks) -> Spi3Strip {
+    let spi: Spi<SPI3> = Spi::new(
+        spi,
+        (pc10.into_alternate(), NoPin::new(), pc12.into_alternate()),
+        SPI_MODE,
+        SPI_HZ.Hz(),
+        clocks,
+    );
+
+    let front = singleton!(: [u8; SPI3_BUFFER] = [0; SPI3_BUFFER]).unwrap();
+    let back = singleton!(: [u8; SPI3_BUFFER] = [0; SPI3_BUFFER]).unwrap();
+    DmaStrip::new(stream, spi.use_dma().tx(), front, back)
+}
+
+/// Board layer for the blade LEDs
+///
+/// Up to three strips driven as one continuous run of LEDs, each clocked
+/// out by DMA so refreshing never stalls the main loop. WS2812 by
+/// default, APA102/SK9822 with the `apa102` feature.
+pub struct LightPorts {
+    lights: BladeLights<Strips>,
+}
+
+impl LightPorts {
+    pub fn new(spi1: Spi1Strip, spi2: Spi2Strip, spi3: Spi3Strip, clock: &dyn Clock) -> Self {
+        let strips = MultiChannel::new((
+            Channel::new(spi1, SPI1_LEDS),
+            Channel::new(spi2, SPI2_LEDS),
+            Channel::new(spi3, SPI3_LEDS),
+        ));
+
+        let mut lights = BladeLights::with_sections(strips, &SHELL_MAP, clock);
+        lights.set_output(OUTPUT);
+        lights.set_power_budget(POWER_BUDGET);
+        lights.set_dithering(DITHERING);
 
-    fn get_next_blink(&self) -> Instant<u32, 1, 1000> {
-        self.sys_timer.now() + BLINK_MSEC.millis()
+        Self { lights }
     }
 
     pub fn set_blade(&mut self, blade: u8, color: RGB8, blink: bool) -> Result<(), &'static str>{
-        let blade = blade as usize;
-        if blade >= LED_NUM {
-            return Err("blade index out of range")
-        }
+        self.lights.set_blade(blade, color, blink)
+    }
 
-        self.led_data[blade] = color;
-        self.blink_mask[blade] = blink;
+    /// Blink pattern for a blade, eg for status indications
+    pub fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        self.lights.set_blink(blade, blink)
+    }
 
-        Ok(())
+    /// Drives the white LED directly on RGBW strips
+    pub fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        self.lights.set_white(blade, white)
     }
 
-    pub fn refresh(&mut self, updated: bool)  {
+    /// Renders the blades and queues them for transfer, without waiting
+    pub fn refresh(&mut self, updated: bool, clock: &dyn Clock) -> Result<(), ChannelError<StripError>> {
+        self.lights.refresh(updated, clock)
+    }
 
-        let mut updated = updated;
-        if self.sys_timer.now() > self.blink_next {
-            self.blink_next = self.get_next_blink();
-            self.blink_on ^= true;
-            updated = true;
+    /// Resets the SPI and DMA of one strip, after repeated errors
+    pub fn restart(&mut self, channel: u8) {
+        let (spi1, spi2, spi3) = self.lights.sink_mut().channels_mut();
+        match channel {
+            0 => spi1.sink_mut().restart(),
+            1 => spi2.sink_mut().restart(),
+            2 => spi3.sink_mut().restart(),
+            _ => {}
         }
+    }
 
-        if !updated {
-            return;
-        }
+    /// Estimated current draw and limiting for the last frame
+    pub fn power(&self) -> &PowerReport {
+        self.lights.power()
+    }
 
-        let mut current_leds = self.led_data.clone();
-        if self.blink_on == false {
-            for i in 0..LED_NUM {
-                if self.blink_mask[i] == true {
-                    current_leds[i] = RGB8::default();
-                }
-            }
-        }
+    /// Starts any frame that was waiting for the previous transfer
+    pub fn poll(&mut self) {
+        let (spi1, spi2, spi3) = self.lights.sink_mut().channels_mut();
+        spi1.sink_mut().poll();
+        spi2.sink_mut().poll();
+        spi3.sink_mut().poll();
+    }
 
-        self.ws.write(current_leds.iter().cloned()).unwrap();
+    /// True while any strip still has a frame waiting behind the one
+    /// going out, rendering another would only replace it
+    pub fn is_busy(&self) -> bool {
+        let (spi1, spi2, spi3) = self.lights.sink().channels();
+        spi1.sink().is_busy() || spi2.sink().is_busy() || spi3.sink().is_busy()
+    }
+}
 
+impl BladeSink for LightPorts {
+    fn set_blade(&mut self, blade: u8, color: RGB8, blink: bool) -> Result<(), &'static str> {
+        LightPorts::set_blade(self, blade, color, blink)
     }
 
+    fn set_white(&mut self, blade: u8, white: u8) -> Result<(), &'static str> {
+        LightPorts::set_white(self, blade, white)
+    }
+
+    fn set_blink(&mut self, blade: u8, blink: Option<Blink>) -> Result<(), &'static str> {
+        LightPorts::set_blink(self, blade, blink)
+    }
 }
@@ -1,32 +1,65 @@
 #![no_main]
 #![no_std]
 
-// Halt on panic
-use panic_halt as _; // panic handler
-
 use cortex_m_rt::entry;
 use stm32f4xx_hal as hal;
 
+use crate::hal::dma::StreamsTuple;
 use crate::hal::pac;
 use crate::hal::pac::TIM2;
 use crate::hal::prelude::*;
 use crate::hal::timer::Counter;
+use crate::hal::watchdog::IndependentWatchdog;
 
-use ws2812_spi as ws2812;
-
+use cortex_m::peripheral::DWT;
+use rand_core::RngCore;
 use rtt_target::rprintln;
 use rtt_target::rtt_init_print;
 
+// Panics are recorded for the next boot, then reset
+mod crash;
+
 mod test_points;
 use test_points::{*};
 
-mod pallet;
+mod sys_timer;
+use sys_timer::SysTimer;
+
+mod frame_timer;
+use frame_timer::FrameTimer;
+
+mod shell_map;
+
+mod dma_strip;
 
 mod light_ports;
 use light_ports::*;
 
-mod effects;
-use effects::*;
+mod usb_console;
+use usb_console::UsbConsole;
+
+mod settings_flash;
+use settings_flash::InternalFlash;
+
+use juicy_core::effects::EffectManager;
+use juicy_core::faults::{FaultCounter, Recovery};
+use juicy_core::frame_stats::FrameStats;
+use juicy_core::settings::{Settings, SettingsStore, SettingsWatch};
+use juicy_core::stream::StreamState;
+
+/// Frames rendered per second, effects still keep their own delays
+const FRAME_HZ: u32 = 200;
+
+/// Failed LED writes in a row before the strip's DMA is set up again
+const REINIT_AFTER_ERRORS: u32 = 10;
+
+/// Resets the board if the main loop stalls this long. The LSI clock
+/// behind it can run up to 50% fast, so the real timeout can be as short
+/// as 4 s, still twice the longest settings flash erase.
+const WATCHDOG_MS: u32 = 6000;
+
+/// How long settings have to stay unchanged before they are saved
+const SETTINGS_SAVE_DELAY_MS: u32 = 10_000;
 
 #[entry]
 fn main() -> ! {
@@ -36,16 +69,28 @@ fn main() -> ! {
 
     // Acquire the device peripherals
     let dp = pac::Peripherals::take().unwrap();
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+
+    // Find out why we last reset before anything can panic again
+    let previous_crash = crash::take_previous();
+    let watchdog_reset = dp.RCC.csr.read().wdgrstf().bit_is_set();
+    dp.RCC.csr.modify(|_, w| w.rmvf().set_bit());
+
+    // The cycle counter measures how long each frame keeps the CPU busy
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
 
     // Configure the RCC (Reset and Clock Control) peripheral to enable GPIO
     let rcc = dp.RCC.constrain();
-    let clocks: hal::rcc::Clocks = rcc.cfgr.sysclk(48.MHz()).freeze();
+    // The RNG peripheral needs the 48MHz PLL output
+    let clocks: hal::rcc::Clocks = rcc.cfgr.sysclk(48.MHz()).require_pll48clk().freeze();
 
-    let mut sys_timer:Counter<TIM2, 1000> = dp.TIM2.counter_ms(&clocks);
-    sys_timer.start(u32::MAX.millis()).unwrap();
+    let mut counter:Counter<TIM2, 1000> = dp.TIM2.counter_ms(&clocks);
+    counter.start(u32::MAX.millis()).unwrap();
+    let sys_timer = SysTimer::new(counter);
 
     let gpioa = dp.GPIOA.split();
-    // let gpiob = dp.GPIOB.split();
+    let gpiob = dp.GPIOB.split();
     let gpioc = dp.GPIOC.split();
     // let gpiod = dp.GPIOD.split();
     // let gpioe = dp.GPIOE.split();
@@ -56,32 +101,166 @@ fn main() -> ! {
     );
     test_point.reset_all();
 
-    //  Initialize Ws2812 LED support
-    let mut buffer = [0u8; (LED_NUM * 12) + 30];
-    let mut lights = LightPorts::new(gpioa.pa5, gpioa.pa7, dp.SPI1, &mut buffer, &clocks, &sys_timer);
+    //  Initialize Ws2812 LED support, one DMA driven strip per SPI
+    let dma1 = StreamsTuple::new(dp.DMA1);
+    let dma2 = StreamsTuple::new(dp.DMA2);
+    let mut lights = LightPorts::new(
+        spi1_strip(gpioa.pa5, gpioa.pa7, dp.SPI1, dma2.3, &clocks),
+        spi2_strip(gpiob.pb13, gpiob.pb15, dp.SPI2, dma1.4, &clocks),
+        spi3_strip(gpioc.pc10, gpioc.pc12, dp.SPI3, dma1.5, &clocks),
+        &sys_timer,
+    );
+
+    // Command console on the USB serial port
+    let mut console = UsbConsole::new(
+        (dp.OTG_FS_GLOBAL, dp.OTG_FS_DEVICE, dp.OTG_FS_PWRCLK),
+        gpioa.pa11,
+        gpioa.pa12,
+        &clocks,
+    );
+
+    match &previous_crash {
+        Some(crash) => {
+            rprintln!("Restarted after a crash, {}", crash);
+            console.report(format_args!("restarted after a crash, {}", crash));
+        }
+        None if watchdog_reset => {
+            rprintln!("Restarted by the watchdog");
+            console.report(format_args!("restarted by the watchdog"));
+        }
+        None => {}
+    }
+
+    // Seed the effects from the hardware random number generator
+    let mut rng = dp.RNG.constrain(&clocks);
+    let seed = rng.next_u32();
 
     // Initialize the effects manager
-    let mut effect_manager = EffectManager::new(&sys_timer);
+    let mut effect_manager = EffectManager::with_seed(&sys_timer, seed);
+
+    // Pick up where the last power cycle left off
+    let mut settings_store = SettingsStore::new(InternalFlash::new(dp.FLASH));
+    let saved_settings = settings_store.load();
+    match &saved_settings {
+        Some(settings) => match settings.apply(&mut effect_manager, &sys_timer) {
+            Ok(()) => rprintln!("Settings restored"),
+            Err(error) => rprintln!("Settings not restored: {}", error),
+        },
+        None => rprintln!("No saved settings, using defaults"),
+    }
+    let mut settings_watch = SettingsWatch::new(SETTINGS_SAVE_DELAY_MS, saved_settings);
+
+    // One frame per tick, sleeping in between
+    let mut frame_timer = FrameTimer::new(dp.TIM3, FRAME_HZ, &clocks);
+    let cycles_per_frame = clocks.sysclk().raw() / FRAME_HZ;
+    let cycles_per_us = clocks.sysclk().raw() / 1_000_000;
+    let mut frame_stats = FrameStats::new(cycles_per_frame);
+
+    // Started last, setup above can take longer than the timeout
+    let mut watchdog = IndependentWatchdog::new(dp.IWDG);
+    watchdog.stop_on_debug(&dp.DBGMCU, true);
+    watchdog.start(WATCHDOG_MS.millis());
 
     rprintln!("Effects Started");
     let mut count: u32 = 0;
+    let mut was_limited = false;
+    let mut light_faults = FaultCounter::new(REINIT_AFTER_ERRORS);
+    let mut retry = false;
+    let mut redraw = false;
 
     loop {
-        // Update visual effects
-        let updated = effect_manager.update(&mut lights, &sys_timer);
+        let ticks = frame_timer.wait();
+        watchdog.feed();
+        if ticks > 1 {
+            frame_stats.record_missed(ticks - 1);
+        }
+        let frame_start = DWT::cycle_count();
+
+        let streamed = console.poll(&mut effect_manager, &mut lights, &light_faults, &sys_timer);
+
+        // Update visual effects, paused while a PC streams frames
+        let updated = match console.stream_state(&sys_timer) {
+            StreamState::Streaming => streamed,
+            StreamState::Stopped => {
+                rprintln!("Stream stopped, back to the playlist");
+                effect_manager.next_effect(&sys_timer);
+                effect_manager.update(&mut lights, &sys_timer)
+            }
+            StreamState::Idle => effect_manager.update(&mut lights, &sys_timer),
+        };
 
         // refresh the ws2812 leds to facilitate blinking behavour
-        lights.refresh(updated);
+        // the frame is clocked out by DMA while the loop carries on
+        // a failed frame is written again on the next pass
+        // while the last frame is still queued the update waits for
+        // the next pass rather than replacing it
+        redraw |= updated;
+        lights.poll();
+        if !lights.is_busy() {
+            match lights.refresh(redraw || retry, &sys_timer) {
+                Ok(()) => {
+                    retry = false;
+                    light_faults.record_ok();
+                }
+                Err(error) => {
+                    retry = true;
+                    match light_faults.record_error(error) {
+                        Recovery::Retry => {
+                            rprintln!("LED write failed on channel {}: {:?}, retrying", error.channel, error.cause);
+                        }
+                        Recovery::Reinit => {
+                            rprintln!("LED writes keep failing on channel {}: {:?}, restarting it", error.channel, error.cause);
+                            lights.restart(error.channel);
+                        }
+                    }
+                }
+            }
+            redraw = false;
+        }
+
+        // Report when the power limit kicks in or lets go
+        let power = *lights.power();
+        if power.is_limited() != was_limited {
+            was_limited = power.is_limited();
+            if was_limited {
+                rprintln!("Power limited: {} mA estimated, scaled to {}/255", power.draw_ma, power.scale);
+            } else {
+                rprintln!("Power within budget: {} mA", power.draw_ma);
+            }
+        }
 
-        // delay 1 msec to reduce overhead
-        // this is a bit mickey mouse but it hunts for now
-        let timeout: fugit::Instant<u32, 1, 1000> = sys_timer.now() + 1.millis();
-        while sys_timer.now() < timeout { }
+        frame_stats.record(DWT::cycle_count().wrapping_sub(frame_start));
 
         count += 1;
-        if count > 1000{
+        if count >= FRAME_HZ {
             count = 0;
             test_point.tp1.toggle();
+
+            let window = frame_stats.window();
+            rprintln!(
+                "Frames: {} us average, {} us max, {}% load, {} overruns, {} missed",
+                window.average / cycles_per_us,
+                window.max / cycles_per_us,
+                window.load_percent,
+                frame_stats.overruns(),
+                frame_stats.missed(),
+            );
+            frame_stats.reset_window();
+
+            let power = lights.power();
+            rprintln!("Power: {} mA estimated, {} frames limited", power.draw_ma, power.limited_frames);
+
+            // Checked once a second, saving only after changes settle
+            if let Some(settings) = settings_watch.poll(Settings::capture(&effect_manager), sys_timer.now()) {
+                match settings_store.save(&settings) {
+                    Ok(()) => rprintln!("Settings saved"),
+                    Err(error) => rprintln!("Settings not saved: {}", error),
+                }
+            }
+
+            if light_faults.errors() > 0 {
+                rprintln!("LED faults: {}", light_faults);
+            }
         }
 
     }}
@@ -0,0 +1,77 @@
+use crate::hal::flash::{FlashExt, LockedFlash};
+use crate::hal::pac::FLASH;
+
+use juicy_core::settings::SettingsFlash;
+
+/// Flash sector holding the settings, kept out of `FLASH` in memory.x
+const SETTINGS_SECTOR: u8 = 11;
+/// Offset of sector 11 from the start of flash
+const SETTINGS_OFFSET: usize = 0xE_0000;
+const SETTINGS_SIZE: usize = 128 * 1024;
+
+/// The reserved settings sector of the internal flash
+pub struct InternalFlash {
+    flash: LockedFlash,
+}
+
+impl InternalFlash {
+    pub fn new(flash: FLASH) -> Self {
+        Self {
+            flash: LockedFlash::new(flash),
+        }
+    }
+}
+
+impl SettingsFlash for InternalFlash {
+    fn size(&self) -> usize {
+        SETTINGS_SIZE
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) {
+        let start = SETTINGS_OFFSET + offset;
+        buffer.copy_from_slice(&self.flash.read()[start..start + buffer.len()]);
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), &'static str> {
+        self.flash
+            .unlocked()
+            .program(SETTINGS_OFFSET + offset, data.iter())
+            .map_err(|_| "flash program failed")
+    }
+
+    /// Erases 32 bits at a time, half the time of the HAL's byte wide
+    /// erase: 1 s typically and 2 s at most for the 128K sector. That
+    /// needs a supply of 2.7 V or more, the board runs at 3.3 V. The CPU
+    /// stalls on any flash read meanwhile.
+    fn erase(&mut self) -> Result<(), &'static str> {
+        // Locked again when this is dropped
+        let _unlocked = self.flash.unlocked();
+        // NOTE(unsafe) the flash is unlocked and nothing else programs it
+        let flash = unsafe { &*FLASH::ptr() };
+
+        while flash.sr.read().bsy().bit_is_set() {}
+        flash.sr.write(|w| {
+            w.operr().set_bit()
+                .wrperr().set_bit()
+                .pgaerr().set_bit()
+                .pgperr().set_bit()
+                .pgserr().set_bit()
+        });
+
+        flash.cr.modify(|_, w| unsafe {
+            w.psize().psize32()
+                .snb().bits(SETTINGS_SECTOR)
+                .ser().set_bit()
+                .pg().clear_bit()
+        });
+        flash.cr.modify(|_, w| w.strt().set_bit());
+        while flash.sr.read().bsy().bit_is_set() {}
+        flash.cr.modify(|_, w| w.ser().clear_bit());
+
+        let sr = flash.sr.read();
+        if sr.operr().bit() || sr.wrperr().bit() || sr.pgaerr().bit() || sr.pgperr().bit() || sr.pgserr().bit() {
+            return Err("flash erase failed");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use juicy_core::frame::NUM_BLADES;
+use juicy_core::power::PowerBudget;
+use juicy_core::output::{ColorOrder, Gamma, OutputStage, WhiteMode};
+#[cfg(feature = "apa102")]
+use juicy_core::apa102::{self, Apa102Color};
+use juicy_core::sections::{Section, SectionMap};
+
+/// Strip type fitted, `smart_leds::RGB8` for WS2812 or
+/// `juicy_core::output::RGBW8` for SK6812 RGBW
+#[cfg(not(feature = "apa102"))]
+pub type LedColor = smart_leds::RGB8;
+
+/// APA102/SK9822 strips, built with `--features apa102`
+#[cfg(feature = "apa102")]
+pub type LedColor = Apa102Color;
+
+/// Order the strip expects its channels in, and the order the driver
+/// sends them
+#[cfg(not(feature = "apa102"))]
+const STRIP_ORDER: ColorOrder = ColorOrder::GRB;
+#[cfg(not(feature = "apa102"))]
+const DRIVER_ORDER: ColorOrder = ColorOrder::GRB;
+#[cfg(feature = "apa102")]
+const STRIP_ORDER: ColorOrder = ColorOrder::BGR;
+#[cfg(feature = "apa102")]
+const DRIVER_ORDER: ColorOrder = apa102::WIRE_ORDER;
+
+/// LEDs on each output channel
+///
+/// A channel with no LEDs is never written to, but its SPI, DMA stream and
+/// pins are still set up by `light_ports` and can't be used for anything
+/// else.
+pub const SPI1_LEDS: usize = 32;
+pub const SPI2_LEDS: usize = 0;
+pub const SPI3_LEDS: usize = 0;
+
+/// LEDs across all channels, numbered SPI1 first, then SPI2, then SPI3
+pub const LED_COUNT: usize = SPI1_LEDS + SPI2_LEDS + SPI3_LEDS;
+
+/// Which LEDs make up each blade, blade 0 first
+///
+/// For larger shells give a blade a run of LEDs with
+/// `Section::Range { start, len }` or scattered ones with
+/// `Section::List(&[..])`, and raise the channel LED counts to match.
+const SECTIONS: [Section; NUM_BLADES] = [
+    Section::Range { start: 0, len: 1 },
+    Section::Range { start: 1, len: 1 },
+    Section::Range { start: 2, len: 1 },
+    Section::Range { start: 3, len: 1 },
+    Section::Range { start: 4, len: 1 },
+    Section::Range { start: 5, len: 1 },
+    Section::Range { start: 6, len: 1 },
+    Section::Range { start: 7, len: 1 },
+    Section::Range { start: 8, len: 1 },
+    Section::Range { start: 9, len: 1 },
+    Section::Range { start: 10, len: 1 },
+    Section::Range { start: 11, len: 1 },
+    Section::Range { start: 12, len: 1 },
+    Section::Range { start: 13, len: 1 },
+    Section::Range { start: 14, len: 1 },
+    Section::Range { start: 15, len: 1 },
+    Section::Range { start: 16, len: 1 },
+    Section::Range { start: 17, len: 1 },
+    Section::Range { start: 18, len: 1 },
+    Section::Range { start: 19, len: 1 },
+    Section::Range { start: 20, len: 1 },
+    Section::Range { start: 21, len: 1 },
+    Section::Range { start: 22, len: 1 },
+    Section::Range { start: 23, len: 1 },
+    Section::Range { start: 24, len: 1 },
+    Section::Range { start: 25, len: 1 },
+    Section::Range { start: 26, len: 1 },
+    Section::Range { start: 27, len: 1 },
+    Section::Range { start: 28, len: 1 },
+    Section::Range { start: 29, len: 1 },
+    Section::Range { start: 30, len: 1 },
+    Section::Range { start: 31, len: 1 },
+];
+
+/// Checked at compile time, a bad table fails the build
+pub static SHELL_MAP: SectionMap = SectionMap::new(&SECTIONS, LED_COUNT);
+
+/// Applied to every LED just before it is clocked out
+///
+/// Gamma 2.2 keeps low fire temperatures from washing out. Trim the white
+/// balance if full white looks tinted, and set the strip order to match
+/// the LEDs fitted (WS2812B are GRB, some clones are RGB, APA102 BGR). On RGBW
+/// strips use `WhiteMode::Calibrated` with the white LED's tint if it is
+/// warm or cool white.
+pub const OUTPUT: OutputStage = OutputStage::new()
+    .with_gamma(Gamma::Gamma22)
+    .with_white_balance(255, 255, 255)
+    .with_color_order(STRIP_ORDER, DRIVER_ORDER)
+    .with_white_mode(WhiteMode::MinOfRgb);
+
+/// About 20mA per channel at full and 1mA idle per LED for WS2812, set
+/// the supply limit to what the LED supply can give with some headroom
+pub const POWER_BUDGET: PowerBudget = PowerBudget::new(20, 1, 2000);
+
+/// Temporal dithering for smooth dim fades. Frames are then written on
+/// every pass of the main loop, so turn it off to cut DMA traffic.
+pub const DITHERING: bool = true;
@@ -0,0 +1,26 @@
+use crate::hal::pac::TIM2;
+use crate::hal::timer::Counter;
+
+use fugit::Instant;
+use juicy_core::clock::Clock;
+
+/// Free running millisecond counter on TIM2, the board's `Clock`
+pub struct SysTimer {
+    counter: Counter<TIM2, 1000>,
+}
+
+impl SysTimer {
+    pub fn new(counter: Counter<TIM2, 1000>) -> Self {
+        Self { counter }
+    }
+
+    pub fn now(&self) -> Instant<u32, 1, 1000> {
+        self.counter.now()
+    }
+}
+
+impl Clock for SysTimer {
+    fn now(&self) -> Instant<u32, 1, 1000> {
+        self.counter.now()
+    }
+}
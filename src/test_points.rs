@@ -64,6 +64,7 @@ impl TestPoints {
     ///
     /// The TestPoints Instance
     ///
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pc0: Pin<'C', 0>,
         pc1: Pin<'C', 1>,
@@ -0,0 +1,180 @@
+use core::fmt::{Display, Write};
+
+use crate::hal::gpio::Pin;
+use crate::hal::otg_fs::{UsbBus, UsbBusType, USB};
+use crate::hal::pac::{OTG_FS_DEVICE, OTG_FS_GLOBAL, OTG_FS_PWRCLK};
+use crate::hal::rcc::Clocks;
+
+use cortex_m::singleton;
+use heapless::String;
+use usb_device::bus::UsbBusAllocator;
+use usb_device::prelude::*;
+use usbd_serial::{SerialPort, USB_CLASS_CDC};
+
+use juicy_core::clock::Clock;
+use juicy_core::console::{execute, parse, Command, LineBuffer, USB_PID, USB_VID};
+use juicy_core::effects::EffectManager;
+use juicy_core::frame::BladeSink;
+use juicy_core::stream::{Decoded, StreamDecoder, StreamState, StreamTimeout};
+
+/// Room for the longest reply, `list` with every effect
+const REPLY_LEN: usize = 512;
+
+/// How long without a streamed frame before the effects take over again
+const STREAM_TIMEOUT_MS: u32 = 2000;
+
+/// Command console on the USB CDC serial port, PA11/PA12
+///
+/// Adalight and TPM2 frames on the same port are written straight to the
+/// blades, see `juicy_core::stream`.
+///
+/// `poll()` has to be called at least every few milliseconds to keep the
+/// USB device enumerated.
+pub struct UsbConsole {
+    device: UsbDevice<'static, UsbBusType>,
+    serial: SerialPort<'static, UsbBusType>,
+    line: LineBuffer,
+    stream: StreamDecoder,
+    stream_timeout: StreamTimeout,
+    /// Sent once a terminal opens the port, see `report`
+    notice: Reply,
+}
+
+impl UsbConsole {
+    /// Can only be called once, the USB bus is statically allocated
+    pub fn new(
+        usb: (OTG_FS_GLOBAL, OTG_FS_DEVICE, OTG_FS_PWRCLK),
+        pa11: Pin<'A', 11>,
+        pa12: Pin<'A', 12>,
+        clocks: &Clocks,
+    ) -> Self {
+        let usb = USB::new(usb, (pa11, pa12), clocks);
+
+        let endpoint_memory = singleton!(: [u32; 1024] = [0; 1024]).unwrap();
+        let bus = singleton!(: UsbBusAllocator<UsbBusType> = UsbBus::new(usb, endpoint_memory)).unwrap();
+
+        let serial = SerialPort::new(bus);
+        let device = UsbDeviceBuilder::new(bus, UsbVidPid(USB_VID, USB_PID))
+            .strings(&[StringDescriptors::default()
+                .manufacturer("PoohCook")
+                .product("Juicy Shell")
+                .serial_number("0001")])
+            .unwrap()
+            .device_class(USB_CLASS_CDC)
+            .build();
+
+        Self {
+            device,
+            serial,
+            line: LineBuffer::new(),
+            stream: StreamDecoder::new(),
+            stream_timeout: StreamTimeout::new(STREAM_TIMEOUT_MS),
+            notice: Reply(String::new()),
+        }
+    }
+
+    /// Queues a line for whoever opens the port next, eg the last crash
+    pub fn report(&mut self, args: core::fmt::Arguments) {
+        let _ = self.notice.write_fmt(args);
+        let _ = self.notice.write_str("\n");
+    }
+
+    /// Services the USB device, runs any complete command lines and
+    /// writes streamed frames to `lights`. `led_faults` is added to the
+    /// `status` reply.
+    ///
+    /// Returns true when a streamed frame was written.
+    pub fn poll(
+        &mut self,
+        manager: &mut EffectManager,
+        lights: &mut dyn BladeSink,
+        led_faults: &dyn Display,
+        clock: &dyn Clock,
+    ) -> bool {
+        if !self.device.poll(&mut [&mut self.serial]) {
+            return false;
+        }
+
+        // Terminals raise DTR when they open the port
+        if !self.notice.0.is_empty() && self.serial.dtr() {
+            let notice = core::mem::take(&mut self.notice.0);
+            self.send(notice.as_bytes());
+        }
+
+        let mut received = [0u8; 64];
+        let count = match self.serial.read(&mut received) {
+            Ok(count) => count,
+            Err(_) => return false,
+        };
+
+        let now = clock.now();
+        let mut streamed = false;
+        for &byte in &received[..count] {
+            let text = match self.stream.push(byte, now) {
+                Decoded::Text(text) => text,
+                Decoded::Pending => continue,
+                Decoded::Frame => {
+                    self.show_frame(lights);
+                    self.stream_timeout.frame_received(now);
+                    streamed = true;
+                    continue;
+                }
+            };
+
+            for &byte in text.as_bytes() {
+                let Some(line) = self.line.push(byte) else {
+                    continue;
+                };
+
+                let mut reply = Reply(String::new());
+                let _ = match line.and_then(parse) {
+                    Ok(Command::Status) => execute(Command::Status, manager, clock, &mut reply)
+                        .and_then(|()| writeln!(reply, "led faults: {}", led_faults)),
+                    Ok(command) => execute(command, manager, clock, &mut reply),
+                    Err(error) => writeln!(reply, "error: {}", error),
+                };
+                self.send(reply.0.as_bytes());
+            }
+        }
+
+        streamed
+    }
+
+    /// Whether frames are being streamed, the effects are paused meanwhile
+    pub fn stream_state(&mut self, clock: &dyn Clock) -> StreamState {
+        self.stream_timeout.poll(clock.now())
+    }
+
+    fn show_frame(&self, lights: &mut dyn BladeSink) {
+        for (blade, &color) in self.stream.frame().iter().enumerate() {
+            let _ = lights.set_blade(blade as u8, color, false);
+            let _ = lights.set_white(blade as u8, 0);
+        }
+    }
+
+    /// Writes as much of `bytes` as the host takes, dropping the rest
+    /// rather than stalling the frame loop
+    fn send(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            match self.serial.write(bytes) {
+                Ok(written) => bytes = &bytes[written..],
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+/// Reply text with the line endings serial terminals expect
+struct Reply(String<REPLY_LEN>);
+
+impl Write for Reply {
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        for (index, part) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.0.push_str("\r\n").map_err(|_| core::fmt::Error)?;
+            }
+            self.0.push_str(part).map_err(|_| core::fmt::Error)?;
+        }
+        Ok(())
+    }
+}